@@ -3,7 +3,7 @@ use std::fs;
 use intcodecomputer::IntcodeComputer;
 
 fn main() {
-    let input: Vec<isize> = fs::read_to_string("input.txt").unwrap().trim().split(",").map(|code| code.parse::<isize>().unwrap()).collect();
+    let input: Vec<i64> = fs::read_to_string("input.txt").unwrap().trim().split(",").map(|code| code.parse::<i64>().unwrap()).collect();
     
     let mut computer = IntcodeComputer::new();
 
@@ -11,12 +11,16 @@ fn main() {
     part1_input[1] = 12;
     part1_input[2] = 2;
     computer.load(&part1_input);
-    computer.run(None);
+    computer.run(None).unwrap();
     println!("Part 1: {}", computer.memory[0]);
     println!();
 
     println!("Part 2:");
 
+    // A mutated program may never halt, so give each attempt a bounded run
+    computer.set_instruction_budget(Some(100_000));
+    computer.set_loop_detection(true);
+
     let mut noun = 0;
     let mut verb = 0;
 
@@ -26,8 +30,7 @@ fn main() {
             program[1] = n;
             program[2] = v;
             computer.load(&program);
-            computer.run(None);
-            if computer.memory[0] == 19690720 {
+            if computer.run(None).is_ok() && computer.memory[0] == 19690720 {
                 noun = n;
                 verb = v;
                 break 'noun;  
@@ -1,9 +1,9 @@
 use std::fs;
 
-use intcodecomputer::IntcodeComputer;
+use intcodecomputer::{IntcodeComputer, RunState};
 
 fn main() {
-    let input: Vec<isize> = fs::read_to_string("input.txt").unwrap().trim().split(",").map(|code| code.parse::<isize>().unwrap()).collect();
+    let input: Vec<i64> = fs::read_to_string("input.txt").unwrap().trim().split(",").map(|code| code.parse::<i64>().unwrap()).collect();
 
     let amp1 = IntcodeComputer::new();
     let amp2 = IntcodeComputer::new();
@@ -14,22 +14,19 @@ fn main() {
     let mut highest_thrust = 0;
     let mut amps = vec![amp1, amp2, amp3, amp4, amp5];
     for combination in generate_combinations() {
-        amps[0].load(&input);
-        amps[1].load(&input);
-        amps[2].load(&input);
-        amps[3].load(&input);
-        amps[4].load(&input);
+        for (amp, phase) in amps.iter_mut().zip(combination) {
+            amp.load(&input);
+            amp.push_input(phase);
+        }
 
-        let mut output = amps[0].run(Some(vec![combination[0], 0]));
-        output = amps[1].run(Some(vec![combination[1], output[0]]));
-        output = amps[2].run(Some(vec![combination[2], output[0]]));
-        output = amps[3].run(Some(vec![combination[3], output[0]]));
-        output = amps[4].run(Some(vec![combination[4], output[0]]));
         let mut amp_number = 0;
         let mut last_output = 0;
-        while output.len() != 0 {
-            last_output = output[0];
-            output = amps[amp_number].run(Some(vec![output[0]]));
+        loop {
+            amps[amp_number].push_input(last_output);
+            match amps[amp_number].run_until_output().unwrap() {
+                RunState::Output(output) => last_output = output,
+                _ => break,
+            }
             amp_number = match amp_number {
                 0 | 1 | 2 | 3 => amp_number + 1,
                 _ => 0,
@@ -42,8 +39,8 @@ fn main() {
     println!("Highest Thrust: {}", highest_thrust);
 }
 
-fn generate_combinations() -> Vec<Vec<isize>> {
-    let mut combinations: Vec<Vec<isize>> = Vec::new();
+fn generate_combinations() -> Vec<Vec<i64>> {
+    let mut combinations: Vec<Vec<i64>> = Vec::new();
 
     for a in 5..=9 {
         for b in 5..=9 {
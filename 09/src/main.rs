@@ -3,15 +3,15 @@ use std::fs;
 use intcodecomputer::IntcodeComputer;
 
 fn main() {
-    let input: Vec<isize> = fs::read_to_string("input.txt").unwrap().trim().split(",").map(|code| code.parse::<isize>().unwrap()).collect();
+    let input: Vec<i64> = fs::read_to_string("input.txt").unwrap().trim().split(",").map(|code| code.parse::<i64>().unwrap()).collect();
 
     let mut computer = IntcodeComputer::new();
 
     computer.load(&input);
-    let outputs = computer.run(Some(vec![1]));
+    let outputs = computer.run(Some(vec![1])).unwrap();
     println!("Boost Keycode: {:?}", outputs);
 
     computer.load(&input);
-    let outputs = computer.run(Some(vec![2]));
+    let outputs = computer.run(Some(vec![2])).unwrap();
     println!("Distress Signal: {:?}", outputs);
 }
@@ -0,0 +1,258 @@
+use crate::word::Word;
+use crate::{IntcodeComputer, IntcodeError, RunState, Snapshot};
+
+use std::collections::VecDeque;
+
+const NEWLINE: u8 = b'\n';
+
+type AsciiRun<W> = Result<(RunState<W>, Vec<AsciiOutput<W>>), IntcodeError<W>>;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub enum AsciiOutput<W: Word = i64> {
+    Text(String),
+    // Anything outside the ASCII range, such as a final answer printed after a map
+    Value(W),
+}
+
+// What the computer did next, a line at a time
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub enum AsciiEvent<W: Word = i64> {
+    // Without its newline. Text left over when the computer stops, such as a prompt, comes as a line too.
+    Line(String),
+    Value(W),
+    AwaitingInput,
+    Halted,
+}
+
+pub fn encode<W: Word>(text: &str) -> Vec<W> {
+    text.chars().map(|character| W::from_usize(character as usize)).collect()
+}
+
+pub fn encode_line<W: Word>(line: &str) -> Vec<W> {
+    let mut codes = encode(line);
+    codes.push(W::from_usize(NEWLINE as usize));
+    codes
+}
+
+fn ascii<W: Word>(code: &W) -> Option<char> {
+    code.to_usize().filter(|&code| code < 128).map(|code| code as u8 as char)
+}
+
+// Runs of codes below 128 become text, everything else is passed through as a value
+pub fn decode<W: Word>(outputs: &[W]) -> Vec<AsciiOutput<W>> {
+    let mut decoded = vec![];
+    for output in outputs {
+        match (ascii(output), decoded.last_mut()) {
+            (Some(character), Some(AsciiOutput::Text(text))) => text.push(character),
+            (Some(character), _) => decoded.push(AsciiOutput::Text(character.to_string())),
+            (None, _) => decoded.push(AsciiOutput::Value(output.clone())),
+        }
+    }
+    decoded
+}
+
+// Wraps a computer for programs that talk in ASCII, so callers deal in strings rather than character codes
+pub struct AsciiComputer<W: Word = i64> {
+    computer: IntcodeComputer<W>,
+    line: String,
+    events: VecDeque<AsciiEvent<W>>,
+}
+
+impl<W: Word> AsciiComputer<W> {
+    pub fn new(computer: IntcodeComputer<W>) -> AsciiComputer<W> {
+        AsciiComputer {
+            computer,
+            line: String::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn load(program: &[W]) -> AsciiComputer<W> {
+        let mut computer = IntcodeComputer::default();
+        computer.load(program);
+        AsciiComputer::new(computer)
+    }
+
+    pub fn computer(&self) -> &IntcodeComputer<W> {
+        &self.computer
+    }
+
+    pub fn computer_mut(&mut self) -> &mut IntcodeComputer<W> {
+        &mut self.computer
+    }
+
+    pub fn into_inner(self) -> IntcodeComputer<W> {
+        self.computer
+    }
+
+    // Also drops any partial line and events not read yet, which belong to the state being replaced
+    pub fn restore(&mut self, snapshot: &Snapshot<W>) {
+        self.computer.restore(snapshot);
+        self.line.clear();
+        self.events.clear();
+    }
+
+    // Queues the text as it is, without a newline
+    pub fn send(&mut self, text: &str) {
+        encode(text).into_iter().for_each(|code| self.computer.push_input(code));
+    }
+
+    pub fn send_line(&mut self, line: &str) {
+        encode_line(line).into_iter().for_each(|code| self.computer.push_input(code));
+    }
+
+    // Runs until the program halts or wants input that has not been sent yet
+    pub fn run(&mut self) -> AsciiRun<W> {
+        let (state, outputs) = self.computer.resume()?;
+        Ok((state, decode(&outputs)))
+    }
+
+    pub fn run_line(&mut self, line: &str) -> AsciiRun<W> {
+        self.send_line(line);
+        self.run()
+    }
+
+    // Runs only as far as the next complete line or value. Once the computer is waiting for input or halted this
+    // keeps saying so, until more input is sent.
+    pub fn next_event(&mut self) -> Result<AsciiEvent<W>, IntcodeError<W>> {
+        while self.events.is_empty() {
+            match self.computer.run_until_output()? {
+                RunState::Output(output) => match ascii(&output) {
+                    Some('\n') => self.events.push_back(AsciiEvent::Line(std::mem::take(&mut self.line))),
+                    Some(character) => self.line.push(character),
+                    None => {
+                        self.take_partial_line();
+                        self.events.push_back(AsciiEvent::Value(output));
+                    },
+                },
+                RunState::AwaitingInput => {
+                    self.take_partial_line();
+                    self.events.push_back(AsciiEvent::AwaitingInput);
+                },
+                RunState::Halted => {
+                    self.take_partial_line();
+                    self.events.push_back(AsciiEvent::Halted);
+                },
+            }
+        }
+        Ok(self.events.pop_front().unwrap())
+    }
+
+    fn take_partial_line(&mut self) {
+        if !self.line.is_empty() {
+            self.events.push_back(AsciiEvent::Line(std::mem::take(&mut self.line)));
+        }
+    }
+
+    // Collects lines up to the point the computer needs input or halts, ready to show as a screen of text
+    pub fn read_lines(&mut self) -> AsciiRun<W> {
+        let mut outputs = vec![];
+        loop {
+            match self.next_event()? {
+                AsciiEvent::Line(line) => outputs.push(AsciiOutput::Text(line)),
+                AsciiEvent::Value(value) => outputs.push(AsciiOutput::Value(value)),
+                AsciiEvent::AwaitingInput => return Ok((RunState::AwaitingInput, outputs)),
+                AsciiEvent::Halted => return Ok((RunState::Halted, outputs)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::BigInt;
+
+    // Echoes every character it is given, forever
+    const ECHO: [i64; 7] = [3,100,4,100,1105,1,0];
+
+    // Prints "Name?" then greets whatever line it reads, then prints 1000 and halts. The terminal's tests use it too.
+    pub(crate) fn greeter() -> Vec<i64> {
+        let mut program = vec![];
+        for character in "Name? ".chars() {
+            program.extend(vec![104, character as i64]);
+        }
+        // Reads a character into [200] and outputs it until it reads a newline
+        let read = program.len() as i64;
+        program.extend(vec![3,200, 1008,200,10,201, 1005,201,read + 14, 4,200, 1105,1,read]);
+        for character in "Hi!\n".chars() {
+            program.extend(vec![104, character as i64]);
+        }
+        program.extend(vec![104,1000,99]);
+        program
+    }
+
+    #[test]
+    fn test_encode_and_decode() {
+        let outputs: Vec<i64> = vec![35,46,10,35,35,10,2000,-1,46];
+
+        assert_eq!(encode_line::<i64>("Go"), vec![71,111,10]);
+        assert_eq!(decode(&outputs), vec![
+            AsciiOutput::Text(String::from("#.\n##\n")),
+            AsciiOutput::Value(2000),
+            AsciiOutput::Value(-1),
+            AsciiOutput::Text(String::from(".")),
+        ]);
+    }
+
+    #[test]
+    fn test_run_line() {
+        let mut computer = AsciiComputer::load(&ECHO);
+
+        assert_eq!(computer.run().unwrap(), (RunState::AwaitingInput, vec![]));
+        assert_eq!(computer.run_line("hello").unwrap(), (RunState::AwaitingInput, vec![AsciiOutput::Text(String::from("hello\n"))]));
+    }
+
+    #[test]
+    fn test_events() {
+        let mut computer = AsciiComputer::load(&greeter());
+
+        assert_eq!(computer.next_event().unwrap(), AsciiEvent::Line(String::from("Name? ")));
+        assert_eq!(computer.next_event().unwrap(), AsciiEvent::AwaitingInput);
+        assert_eq!(computer.next_event().unwrap(), AsciiEvent::AwaitingInput);
+
+        computer.send_line("Ada");
+
+        assert_eq!(computer.next_event().unwrap(), AsciiEvent::Line(String::from("AdaHi!")));
+        assert_eq!(computer.next_event().unwrap(), AsciiEvent::Value(1000));
+        assert_eq!(computer.next_event().unwrap(), AsciiEvent::Halted);
+    }
+
+    #[test]
+    fn test_restore_drops_pending_output() {
+        // The value ends the line early, leaving itself queued behind it
+        let mut computer = AsciiComputer::<i64>::load(&[104,65,104,1000,99]);
+        let snapshot = computer.computer().snapshot();
+        assert_eq!(computer.next_event().unwrap(), AsciiEvent::Line(String::from("A")));
+
+        computer.restore(&snapshot);
+
+        assert_eq!(computer.next_event().unwrap(), AsciiEvent::Line(String::from("A")));
+        assert_eq!(computer.next_event().unwrap(), AsciiEvent::Value(1000));
+    }
+
+    #[test]
+    fn test_read_lines() {
+        let mut computer = AsciiComputer::load(&greeter());
+
+        assert_eq!(computer.read_lines().unwrap(), (RunState::AwaitingInput, vec![AsciiOutput::Text(String::from("Name? "))]));
+
+        computer.send_line("Bo");
+
+        assert_eq!(computer.read_lines().unwrap(), (RunState::Halted, vec![AsciiOutput::Text(String::from("BoHi!")), AsciiOutput::Value(1000)]));
+    }
+
+    #[test]
+    fn test_big_values() {
+        let huge = BigInt::from(i128::MAX);
+        let program = vec![BigInt::from(104i64), huge.clone(), BigInt::from(104i64), BigInt::from(65i64), BigInt::from(99i64)];
+
+        let mut computer = AsciiComputer::load(&program);
+
+        assert_eq!(computer.run().unwrap(), (RunState::Halted, vec![AsciiOutput::Value(huge), AsciiOutput::Text(String::from("A"))]));
+    }
+}
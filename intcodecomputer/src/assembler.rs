@@ -0,0 +1,381 @@
+use crate::{Opcode, ParameterMode};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum AssemblerErrorKind {
+    UnknownMnemonic(String),
+    UnknownDirective(String),
+    WrongOperandCount { mnemonic: String, expected: usize, found: usize },
+    InvalidOperand(String),
+    InvalidLabel(String),
+    UnknownSymbol(String),
+    DuplicateSymbol(String),
+    WriteToImmediate,
+    // An expression whose value does not fit in 64 bits
+    Overflow(String),
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct AssemblerError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: AssemblerErrorKind,
+}
+
+impl fmt::Display for AssemblerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            AssemblerErrorKind::UnknownMnemonic(mnemonic) => write!(f, "unknown mnemonic {}", mnemonic),
+            AssemblerErrorKind::UnknownDirective(directive) => write!(f, "unknown directive {}", directive),
+            AssemblerErrorKind::WrongOperandCount { mnemonic, expected, found } => write!(f, "{} expects {} operands, found {}", mnemonic, expected, found),
+            AssemblerErrorKind::InvalidOperand(operand) => write!(f, "invalid operand {}", operand),
+            AssemblerErrorKind::InvalidLabel(label) => write!(f, "invalid label {}", label),
+            AssemblerErrorKind::UnknownSymbol(symbol) => write!(f, "unknown symbol {}", symbol),
+            AssemblerErrorKind::DuplicateSymbol(symbol) => write!(f, "symbol {} is already defined", symbol),
+            AssemblerErrorKind::WriteToImmediate => write!(f, "result can not be written to an immediate operand"),
+            AssemblerErrorKind::Overflow(expression) => write!(f, "{} does not fit in 64 bits", expression),
+        }
+    }
+}
+
+impl Error for AssemblerError {}
+
+#[derive(Clone, Copy)]
+enum Symbol {
+    Value(i64),
+    // Resolves to [rb+offset] when used as a memory operand
+    Relative(i64),
+}
+
+// A piece of source text with the 1-based line and column it starts at
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Token<'a> {
+    fn error(&self, kind: AssemblerErrorKind) -> AssemblerError {
+        AssemblerError { line: self.line, column: self.column, kind }
+    }
+
+    fn trimmed(&self) -> Token<'a> {
+        let leading = self.text.len() - self.text.trim_start().len();
+        Token { text: self.text.trim(), line: self.line, column: self.column + leading }
+    }
+
+    fn split_at(&self, index: usize) -> (Token<'a>, Token<'a>) {
+        (
+            Token { text: &self.text[..index], line: self.line, column: self.column },
+            Token { text: &self.text[index..], line: self.line, column: self.column + index },
+        )
+    }
+
+    fn split_list(&self) -> Vec<Token<'a>> {
+        if self.text.trim().is_empty() {
+            return vec![];
+        }
+        let mut tokens = vec![];
+        let mut rest = *self;
+        while let Some(index) = rest.text.find(',') {
+            let (token, remainder) = rest.split_at(index);
+            tokens.push(token.trimmed());
+            rest = remainder.split_at(1).1;
+        }
+        tokens.push(rest.trimmed());
+        tokens
+    }
+}
+
+enum Item<'a> {
+    Instruction { mnemonic: Token<'a>, opcode: Opcode, operands: Vec<Token<'a>> },
+    Data(Vec<Token<'a>>),
+}
+
+// Assembly syntax, one statement per line with `;` starting a comment:
+//   loop:  ADD [rb+1], #5, [total]
+//          JUMPTRUE [flag], #loop
+//   total: .data 0
+//          .equ LIMIT 10
+//          .rb counter 2      ; [counter] assembles to [rb+2]
+pub fn assemble(source: &str) -> Result<Vec<i64>, AssemblerError> {
+    let mut symbols: HashMap<&str, Symbol> = HashMap::new();
+    let mut items: Vec<Item> = vec![];
+    let mut address: usize = 0;
+
+    for (index, text) in source.lines().enumerate() {
+        let text = match text.find(';') {
+            Some(comment) => &text[..comment],
+            None => text,
+        };
+        let mut rest = Token { text, line: index + 1, column: 1 }.trimmed();
+
+        while let Some(colon) = label_end(rest.text) {
+            let (label, remainder) = rest.split_at(colon);
+            define(&mut symbols, label, Symbol::Value(address as i64))?;
+            rest = remainder.split_at(1).1.trimmed();
+        }
+        if rest.text.is_empty() {
+            continue;
+        }
+
+        let (name, arguments) = match rest.text.find(char::is_whitespace) {
+            Some(end) => {
+                let (name, arguments) = rest.split_at(end);
+                (name, arguments.trimmed())
+            },
+            None => rest.split_at(rest.text.len()),
+        };
+
+        match name.text {
+            ".data" => {
+                let values = arguments.split_list();
+                address += values.len();
+                items.push(Item::Data(values));
+            },
+            ".equ" | ".rb" => {
+                let parts: Vec<&str> = arguments.text.split_whitespace().collect();
+                if parts.len() != 2 {
+                    return Err(name.error(AssemblerErrorKind::WrongOperandCount { mnemonic: name.text.to_string(), expected: 2, found: parts.len() }));
+                }
+                let (symbol, value) = arguments.split_at(parts[0].len());
+                let value = value.trimmed();
+                let value = value.text.parse::<i64>().map_err(|_| value.error(AssemblerErrorKind::InvalidOperand(value.text.to_string())))?;
+                define(&mut symbols, symbol, match name.text {
+                    ".equ" => Symbol::Value(value),
+                    _ => Symbol::Relative(value),
+                })?;
+            },
+            directive if directive.starts_with('.') => return Err(name.error(AssemblerErrorKind::UnknownDirective(directive.to_string()))),
+            mnemonic => {
+                let opcode = Opcode::from_mnemonic(mnemonic).ok_or_else(|| name.error(AssemblerErrorKind::UnknownMnemonic(mnemonic.to_string())))?;
+                let operands = arguments.split_list();
+                if operands.len() != opcode.parameter_count() {
+                    return Err(name.error(AssemblerErrorKind::WrongOperandCount {
+                        mnemonic: mnemonic.to_string(),
+                        expected: opcode.parameter_count(),
+                        found: operands.len(),
+                    }));
+                }
+                address += opcode.parameter_count() + 1;
+                items.push(Item::Instruction { mnemonic: name, opcode, operands });
+            },
+        }
+    }
+
+    let mut program = Vec::with_capacity(address);
+    for item in items {
+        match item {
+            Item::Data(values) => for value in values {
+                program.push(evaluate(&symbols, value)?);
+            },
+            Item::Instruction { mnemonic, opcode, operands } => {
+                let operands = operands.into_iter()
+                    .map(|operand| parse_operand(&symbols, operand))
+                    .collect::<Result<Vec<(ParameterMode, i64)>, AssemblerError>>()?;
+                if opcode.write_parameters().iter().any(|&index| operands[index].0 == ParameterMode::ImmediateMode) {
+                    return Err(mnemonic.error(AssemblerErrorKind::WriteToImmediate));
+                }
+
+                let mode_digits: usize = operands.iter().enumerate()
+                    .map(|(index, (mode, _))| mode.code() * 10_usize.pow(index as u32 + 2))
+                    .sum();
+                program.push((opcode.code() + mode_digits) as i64);
+                program.extend(operands.into_iter().map(|(_, value)| value));
+            },
+        }
+    }
+
+    Ok(program)
+}
+
+fn label_end(text: &str) -> Option<usize> {
+    let colon = text.find(':')?;
+    match text[..colon].contains(char::is_whitespace) {
+        true => None,
+        false => Some(colon),
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut characters = text.chars();
+    match characters.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => characters.all(|character| character.is_ascii_alphanumeric() || character == '_'),
+        _ => false,
+    }
+}
+
+fn define<'a>(symbols: &mut HashMap<&'a str, Symbol>, name: Token<'a>, symbol: Symbol) -> Result<(), AssemblerError> {
+    if !is_identifier(name.text) || name.text == "rb" {
+        return Err(name.error(AssemblerErrorKind::InvalidLabel(name.text.to_string())));
+    }
+    if symbols.insert(name.text, symbol).is_some() {
+        return Err(name.error(AssemblerErrorKind::DuplicateSymbol(name.text.to_string())));
+    }
+    Ok(())
+}
+
+fn parse_operand(symbols: &HashMap<&str, Symbol>, operand: Token) -> Result<(ParameterMode, i64), AssemblerError> {
+    let invalid = || operand.error(AssemblerErrorKind::InvalidOperand(operand.text.to_string()));
+
+    if operand.text.starts_with('#') {
+        return Ok((ParameterMode::ImmediateMode, evaluate(symbols, operand.split_at(1).1.trimmed())?));
+    }
+    if !(operand.text.starts_with('[') && operand.text.ends_with(']')) {
+        return Err(invalid());
+    }
+    let inner = operand.split_at(1).1.split_at(operand.text.len() - 2).0.trimmed();
+
+    if inner.text == "rb" {
+        return Ok((ParameterMode::RelativeMode, 0));
+    }
+    if inner.text.starts_with("rb") && inner.text[2..].trim_start().starts_with(['+', '-']) {
+        return Ok((ParameterMode::RelativeMode, evaluate(symbols, inner.split_at(2).1.trimmed())?));
+    }
+    if let Some(Symbol::Relative(offset)) = symbols.get(inner.text) {
+        return Ok((ParameterMode::RelativeMode, *offset));
+    }
+    Ok((ParameterMode::PositionalMode, evaluate(symbols, inner)?))
+}
+
+// Sums numbers and symbols joined by + and -, e.g. `buffer+2` or `-1`
+fn evaluate(symbols: &HashMap<&str, Symbol>, expression: Token) -> Result<i64, AssemblerError> {
+    if expression.text.is_empty() {
+        return Err(expression.error(AssemblerErrorKind::InvalidOperand(String::new())));
+    }
+    let mut total: i64 = 0;
+    let mut rest = expression;
+
+    loop {
+        let sign = match rest.text.chars().next() {
+            Some('-') => -1,
+            _ => 1,
+        };
+        if rest.text.starts_with(['+', '-']) {
+            rest = rest.split_at(1).1.trimmed();
+        }
+        let end = rest.text.find(['+', '-']).unwrap_or(rest.text.len());
+        let (term, remainder) = rest.split_at(end);
+        let term = term.trimmed();
+
+        let value = match term.text.parse::<i64>() {
+            Ok(value) => value,
+            Err(_) if is_identifier(term.text) => match symbols.get(term.text) {
+                Some(Symbol::Value(value)) | Some(Symbol::Relative(value)) => *value,
+                None => return Err(term.error(AssemblerErrorKind::UnknownSymbol(term.text.to_string()))),
+            },
+            Err(_) => return Err(term.error(AssemblerErrorKind::InvalidOperand(term.text.to_string()))),
+        };
+        total = match value.checked_mul(sign).and_then(|term| total.checked_add(term)) {
+            Some(total) => total,
+            None => return Err(expression.error(AssemblerErrorKind::Overflow(expression.text.to_string()))),
+        };
+
+        if remainder.text.is_empty() {
+            return Ok(total);
+        }
+        rest = remainder;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disassembler::{disassemble, listing};
+    use crate::IntcodeComputer;
+
+    #[test]
+    fn test_assemble_modes() {
+        let program = assemble("ADD [rb+3], #5, [12]\nOUTPUT [rb-2]\nHALT").unwrap();
+
+        assert_eq!(program, vec![1201,3,5,12,204,-2,99]);
+    }
+
+    #[test]
+    fn test_labels_and_data() {
+        let source = "
+            ; count down from the first input, printing each value
+                    INPUT [counter]
+            loop:   OUTPUT [counter]
+                    ADD [counter], #-1, [counter]
+                    JUMPTRUE [counter], #loop
+                    HALT
+            counter: .data 0
+        ";
+        let program = assemble(source).unwrap();
+        let mut computer = IntcodeComputer::new();
+        computer.load(&program);
+
+        assert_eq!(program[1], 12);
+        assert_eq!(computer.run(Some(vec![3])).unwrap(), vec![3,2,1]);
+    }
+
+    #[test]
+    fn test_symbols() {
+        let source = "
+            .equ LIMIT 7
+            .rb local 1
+            RELATIVEBASE #end+1
+            ADD #LIMIT, #0, [local]
+            OUTPUT [rb+local]
+            OUTPUT #end
+            end: HALT
+        ";
+
+        assert_eq!(assemble(source).unwrap(), vec![109,11,21101,7,0,1,204,1,104,10,99]);
+    }
+
+    #[test]
+    fn test_round_trip_through_disassembler() {
+        let program = vec![1105,1,7,21101,3,-4,-1,204,-2,99,12345];
+        let source: String = listing(&disassemble(&program)).lines()
+            .map(|line| format!("{}\n", &line[6..]))
+            .collect();
+
+        assert_eq!(assemble(&source).unwrap(), program);
+    }
+
+    #[test]
+    fn test_unknown_mnemonic() {
+        let error = assemble("HALT\n  loop: JUMP #1, #loop").unwrap_err();
+
+        assert_eq!(error, AssemblerError { line: 2, column: 9, kind: AssemblerErrorKind::UnknownMnemonic("JUMP".to_string()) });
+        assert_eq!(error.to_string(), "line 2, column 9: unknown mnemonic JUMP");
+    }
+
+    #[test]
+    fn test_wrong_operand_count() {
+        let error = assemble("ADD #1, #2").unwrap_err();
+
+        assert_eq!(error, AssemblerError {
+            line: 1,
+            column: 1,
+            kind: AssemblerErrorKind::WrongOperandCount { mnemonic: "ADD".to_string(), expected: 3, found: 2 },
+        });
+    }
+
+    #[test]
+    fn test_operand_errors() {
+        assert_eq!(assemble("OUTPUT 5").unwrap_err(), AssemblerError { line: 1, column: 8, kind: AssemblerErrorKind::InvalidOperand("5".to_string()) });
+        assert_eq!(assemble("OUTPUT [missing]").unwrap_err(), AssemblerError { line: 1, column: 9, kind: AssemblerErrorKind::UnknownSymbol("missing".to_string()) });
+        assert_eq!(assemble("INPUT #1").unwrap_err(), AssemblerError { line: 1, column: 1, kind: AssemblerErrorKind::WriteToImmediate });
+        assert_eq!(assemble("a: HALT\na: HALT").unwrap_err(), AssemblerError { line: 2, column: 1, kind: AssemblerErrorKind::DuplicateSymbol("a".to_string()) });
+        assert_eq!(assemble(".word 1").unwrap_err(), AssemblerError { line: 1, column: 1, kind: AssemblerErrorKind::UnknownDirective(".word".to_string()) });
+    }
+
+    #[test]
+    fn test_overflow() {
+        let error = assemble("HALT\n.data 9223372036854775807+1").unwrap_err();
+
+        assert_eq!(error, AssemblerError { line: 2, column: 7, kind: AssemblerErrorKind::Overflow("9223372036854775807+1".to_string()) });
+        assert_eq!(error.to_string(), "line 2, column 7: 9223372036854775807+1 does not fit in 64 bits");
+        assert_eq!(assemble(".data -9223372036854775807-1").unwrap(), vec![i64::MIN]);
+    }
+}
@@ -0,0 +1,46 @@
+use std::env;
+use std::process;
+
+use intcodecomputer::controlflow::observe_jumps;
+use intcodecomputer::{load_program, parse_inputs, ControlFlowGraph, IntcodeComputer};
+
+// Prints the program's control flow graph as Graphviz DOT. With inputs the program is run first and the jumps
+// it takes are merged in.
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    if arguments.is_empty() {
+        eprintln!("usage: cfg <program> [input...]");
+        process::exit(1);
+    }
+    let program = match load_program(&arguments[0]) {
+        Ok(program) => program,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    };
+    let inputs = match parse_inputs(&arguments[1..]) {
+        Ok(inputs) => inputs,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    };
+
+    let graph = match inputs.is_empty() {
+        true => ControlFlowGraph::build(&program),
+        false => {
+            let mut computer = IntcodeComputer::new();
+            computer.load(&program);
+            inputs.into_iter().for_each(|input| computer.push_input(input));
+            match observe_jumps(&mut computer) {
+                Ok(jumps) => ControlFlowGraph::build_with(&program, &jumps),
+                Err(error) => {
+                    eprintln!("Run stopped: {}", error);
+                    process::exit(1);
+                },
+            }
+        },
+    };
+    print!("{}", graph.to_dot());
+}
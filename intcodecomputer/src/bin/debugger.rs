@@ -0,0 +1,40 @@
+use std::env;
+use std::io;
+use std::process;
+
+use intcodecomputer::debugger::Debugger;
+use intcodecomputer::{load_program, IntcodeComputer};
+
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    let path = match arguments.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: debugger <program> [budget]");
+            process::exit(1);
+        },
+    };
+    // Bounds each continue, which otherwise never returns on a program that does not stop
+    let budget = match arguments.get(1).map(|budget| budget.parse::<usize>()) {
+        Some(Ok(budget)) => Some(budget),
+        Some(Err(_)) => {
+            eprintln!("usage: debugger <program> [budget]");
+            process::exit(1);
+        },
+        None => None,
+    };
+    let program = match load_program(path) {
+        Ok(program) => program,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    };
+
+    let mut computer = IntcodeComputer::new();
+    computer.set_instruction_budget(budget);
+    computer.load(&program);
+
+    let stdin = io::stdin();
+    Debugger::new(computer).run(stdin.lock(), io::stdout()).unwrap();
+}
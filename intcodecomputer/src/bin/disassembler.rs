@@ -0,0 +1,45 @@
+use std::env;
+use std::process;
+
+use intcodecomputer::disassembler::{disassemble, disassemble_from, listing, trace_entry_points};
+use intcodecomputer::{load_program, parse_inputs, IntcodeComputer};
+
+// With inputs the program is run first so only code it reaches is decoded, using the memory as it was left
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    if arguments.is_empty() {
+        eprintln!("usage: disassembler <program> [input...]");
+        process::exit(1);
+    }
+    let program = match load_program(&arguments[0]) {
+        Ok(program) => program,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    };
+    let inputs = match parse_inputs(&arguments[1..]) {
+        Ok(inputs) => inputs,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    };
+
+    let lines = match inputs.is_empty() {
+        true => disassemble(&program),
+        false => {
+            let mut computer = IntcodeComputer::new();
+            computer.load(&program);
+            inputs.into_iter().for_each(|input| computer.push_input(input));
+            match trace_entry_points(&mut computer) {
+                Ok(entry_points) => disassemble_from(computer.memory.image(), &entry_points),
+                Err(error) => {
+                    eprintln!("Trace stopped: {}", error);
+                    process::exit(1);
+                },
+            }
+        },
+    };
+    print!("{}", listing(&lines));
+}
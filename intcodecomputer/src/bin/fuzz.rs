@@ -0,0 +1,33 @@
+use std::env;
+use std::process;
+use std::str::FromStr;
+
+use intcodecomputer::fuzz::{fuzz, CONFIGURATIONS};
+
+const DEFAULT_CASES: usize = 10000;
+
+// Compares the computer against the reference interpreter, printing the shrunk case if they disagree
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    let seed = argument(arguments.first(), 0);
+    let cases = argument(arguments.get(1), DEFAULT_CASES);
+
+    match fuzz(seed, cases, &CONFIGURATIONS) {
+        Ok(()) => println!("{} cases from seed {} agree", cases, seed),
+        Err(failure) => {
+            println!("{}", failure);
+            process::exit(1);
+        },
+    }
+}
+
+fn argument<T: FromStr>(argument: Option<&String>, default: T) -> T {
+    match argument.map(|text| text.parse::<T>()) {
+        None => default,
+        Some(Ok(value)) => value,
+        Some(Err(_)) => {
+            eprintln!("usage: fuzz [seed] [cases]");
+            process::exit(1);
+        },
+    }
+}
@@ -0,0 +1,37 @@
+use std::env;
+use std::process;
+
+use intcodecomputer::{load_program, parse_inputs, IntcodeComputer};
+
+const BLOCK_LIMIT: usize = 20;
+
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    if arguments.is_empty() {
+        eprintln!("usage: profiler <program> [input...]");
+        process::exit(1);
+    }
+    let program = match load_program(&arguments[0]) {
+        Ok(program) => program,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    };
+    let inputs = match parse_inputs(&arguments[1..]) {
+        Ok(inputs) => inputs,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    };
+
+    let mut computer = IntcodeComputer::new();
+    computer.set_profiling(true);
+    computer.load(&program);
+    match computer.run(Some(inputs)) {
+        Ok(outputs) => println!("outputs: {:?}", outputs),
+        Err(error) => println!("stopped: {}", error),
+    }
+    print!("{}", computer.profile().unwrap().report(BLOCK_LIMIT));
+}
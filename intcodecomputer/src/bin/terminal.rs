@@ -0,0 +1,55 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::process;
+
+use intcodecomputer::terminal::Terminal;
+use intcodecomputer::{load_program, IntcodeComputer};
+
+// A script, such as one written by :history, is played first and the session then carries on from stdin
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    if arguments.is_empty() {
+        eprintln!("usage: terminal <program> [script]");
+        process::exit(1);
+    }
+    let program = match load_program(&arguments[0]) {
+        Ok(program) => program,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    };
+
+    let mut computer = IntcodeComputer::new();
+    computer.load(&program);
+    let mut terminal = Terminal::new(computer);
+
+    let mut stdout = io::stdout();
+    if let Some(path) = arguments.get(1) {
+        let script = match File::open(path) {
+            Ok(file) => BufReader::new(file),
+            Err(error) => {
+                eprintln!("Could not open {}: {}", path, error);
+                process::exit(1);
+            },
+        };
+        match terminal.replay(script, &mut stdout) {
+            Ok(true) => (),
+            Ok(false) => return,
+            Err(error) => {
+                eprintln!("Could not play {}: {}", path, error);
+                process::exit(1);
+            },
+        }
+        // The script has already shown the program halting, and there is nothing left to type to it
+        if terminal.halted() {
+            return;
+        }
+    }
+    let stdin = io::stdin();
+    if let Err(error) = terminal.run(stdin.lock(), stdout) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,289 @@
+use crate::disassembler::{disassemble_from, jump_condition, jump_target, Line};
+use crate::word::Word;
+use crate::{IntcodeComputer, IntcodeError, Opcode, StepOutcome};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+#[derive(Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy)]
+pub enum EdgeKind {
+    Fallthrough,
+    Jump,
+    // Seen while running, typically the target of a jump through memory
+    Dynamic,
+}
+
+// Edges run between the start addresses of blocks
+#[derive(Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub struct Block {
+    pub start: usize,
+    // The address just past the block's last instruction
+    pub end: usize,
+    pub instructions: Vec<usize>,
+}
+
+impl Block {
+    pub fn last_instruction(&self) -> usize {
+        self.instructions[self.instructions.len() - 1]
+    }
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct ControlFlowGraph<W: Word = i64> {
+    lines: BTreeMap<usize, Line<W>>,
+    blocks: BTreeMap<usize, Block>,
+    edges: BTreeSet<Edge>,
+    unresolved: BTreeSet<usize>,
+}
+
+impl<W: Word> ControlFlowGraph<W> {
+    // Only code reachable from address 0 through fallthrough and immediate jump targets is found
+    pub fn build(program: &[W]) -> ControlFlowGraph<W> {
+        ControlFlowGraph::build_with(program, &[])
+    }
+
+    // Adds (jump address, target) pairs observed at run time, which can reach code the static analysis cannot
+    pub fn build_with(program: &[W], dynamic_edges: &[(usize, usize)]) -> ControlFlowGraph<W> {
+        let entry_points: Vec<usize> = Some(0).into_iter().chain(dynamic_edges.iter().map(|&(_, to)| to)).collect();
+        let lines: BTreeMap<usize, Line<W>> = disassemble_from(program, &entry_points).into_iter()
+            .filter(|line| matches!(line, Line::Instruction { .. }))
+            .map(|line| (line.address(), line))
+            .collect();
+
+        let mut leaders: BTreeSet<usize> = entry_points.iter().copied().filter(|address| lines.contains_key(address)).collect();
+        let mut falls_through_to = BTreeSet::new();
+        for line in lines.values() {
+            if let Line::Instruction { address, opcode, operands } = line {
+                let next = address + line.size();
+                match opcode {
+                    Opcode::JumpTrue | Opcode::JumpFalse => {
+                        leaders.extend(jump_target(*opcode, operands).filter(|target| lines.contains_key(target)));
+                        leaders.insert(next);
+                    },
+                    Opcode::Halt => {
+                        leaders.insert(next);
+                    },
+                    _ => (),
+                }
+                if *opcode != Opcode::Halt && jump_condition(*opcode, operands) != Some(true) {
+                    falls_through_to.insert(next);
+                }
+            }
+        }
+        // Code only reached by a jump still starts a block
+        leaders.extend(lines.keys().filter(|address| !falls_through_to.contains(address)));
+        leaders.retain(|address| lines.contains_key(address));
+
+        let mut graph = ControlFlowGraph {
+            lines,
+            blocks: BTreeMap::new(),
+            edges: BTreeSet::new(),
+            unresolved: BTreeSet::new(),
+        };
+        for &start in &leaders {
+            let mut block = Block { start, end: start, instructions: vec![] };
+            while let Some(line) = graph.lines.get(&block.end) {
+                if !block.instructions.is_empty() && leaders.contains(&block.end) {
+                    break;
+                }
+                block.instructions.push(block.end);
+                block.end += line.size();
+            }
+            graph.blocks.insert(start, block);
+        }
+        graph.add_static_edges();
+        for &(from, to) in dynamic_edges {
+            graph.add_dynamic_edge(from, to);
+        }
+        graph
+    }
+
+    fn add_static_edges(&mut self) {
+        let mut edges = vec![];
+        for block in self.blocks.values() {
+            let (opcode, operands) = match &self.lines[&block.last_instruction()] {
+                Line::Instruction { opcode, operands, .. } => (*opcode, operands),
+                Line::Data { .. } => continue,
+            };
+            let condition = jump_condition(opcode, operands);
+            if opcode != Opcode::Halt && condition != Some(true) && self.blocks.contains_key(&block.end) {
+                edges.push(Edge { from: block.start, to: block.end, kind: EdgeKind::Fallthrough });
+            }
+            if let Opcode::JumpTrue | Opcode::JumpFalse = opcode {
+                match (condition, jump_target(opcode, operands)) {
+                    (Some(false), _) => (),
+                    (_, Some(target)) if self.blocks.contains_key(&target) => edges.push(Edge { from: block.start, to: target, kind: EdgeKind::Jump }),
+                    _ => {
+                        self.unresolved.insert(block.last_instruction());
+                    },
+                }
+            }
+        }
+        self.edges.extend(edges);
+    }
+
+    fn add_dynamic_edge(&mut self, from: usize, to: usize) {
+        let source = match self.block_containing(from) {
+            Some(block) => block.start,
+            None => return,
+        };
+        if !self.blocks.contains_key(&to) || self.edges.iter().any(|edge| edge.from == source && edge.to == to) {
+            return;
+        }
+        self.edges.insert(Edge { from: source, to, kind: EdgeKind::Dynamic });
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.values()
+    }
+
+    pub fn block(&self, start: usize) -> Option<&Block> {
+        self.blocks.get(&start)
+    }
+
+    pub fn block_containing(&self, address: usize) -> Option<&Block> {
+        self.blocks.range(..=address).next_back()
+            .map(|(_, block)| block)
+            .filter(|block| block.instructions.contains(&address))
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = &Edge> {
+        self.edges.iter()
+    }
+
+    pub fn successors(&self, start: usize) -> Vec<usize> {
+        self.edges.iter().filter(|edge| edge.from == start).map(|edge| edge.to).collect()
+    }
+
+    // Addresses of jumps whose target is only known at run time
+    pub fn unresolved(&self) -> impl Iterator<Item = &usize> {
+        self.unresolved.iter()
+    }
+
+    // Graphviz source with each block's disassembly as its label. Jumps are solid, fallthroughs dashed,
+    // dynamic edges blue, and unresolved jumps lead to a ? node.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cfg {\n    node [shape=box, fontname=\"monospace\"];\n");
+        for block in self.blocks.values() {
+            let label: String = block.instructions.iter()
+                .map(|address| format!("{}\\l", escape(&self.lines[address].to_string())))
+                .collect();
+            let _ = writeln!(dot, "    b{} [label=\"{}\"];", block.start, label);
+        }
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Fallthrough => "style=dashed",
+                EdgeKind::Jump => "style=solid",
+                EdgeKind::Dynamic => "style=solid, color=blue",
+            };
+            let _ = writeln!(dot, "    b{} -> b{} [{}];", edge.from, edge.to, style);
+        }
+        for address in &self.unresolved {
+            if let Some(block) = self.block_containing(*address) {
+                let _ = writeln!(dot, "    unresolved{} [label=\"?\", shape=circle];", address);
+                let _ = writeln!(dot, "    b{} -> unresolved{} [style=dotted];", block.start, address);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// Runs the computer until it halts or needs input, returning every jump it took
+pub fn observe_jumps<W: Word>(computer: &mut IntcodeComputer<W>) -> Result<Vec<(usize, usize)>, IntcodeError<W>> {
+    let mut jumps = BTreeSet::new();
+    while let StepOutcome::Executed(step) = computer.step()? {
+        if let Some(target) = step.jump {
+            jumps.insert((step.address, target));
+        }
+    }
+    Ok(jumps.into_iter().collect())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_program;
+    use std::fs;
+
+    // Counts mem[13] down from the input to zero, outputting each value
+    const COUNTDOWN: [i64; 14] = [3,13,4,13,1001,13,-1,13,1005,13,2,99,0,0];
+
+    #[test]
+    fn test_blocks_and_edges() {
+        let graph = ControlFlowGraph::build(&COUNTDOWN);
+
+        assert_eq!(graph.blocks().map(|block| (block.start, block.end)).collect::<Vec<(usize, usize)>>(), vec![(0,2),(2,11),(11,12)]);
+        assert_eq!(graph.block(2).unwrap().instructions, vec![2,4,8]);
+        assert_eq!(graph.edges().copied().collect::<Vec<Edge>>(), vec![
+            Edge { from: 0, to: 2, kind: EdgeKind::Fallthrough },
+            Edge { from: 2, to: 2, kind: EdgeKind::Jump },
+            Edge { from: 2, to: 11, kind: EdgeKind::Fallthrough },
+        ]);
+        assert_eq!(graph.unresolved().count(), 0);
+    }
+
+    #[test]
+    fn test_constant_conditions() {
+        // An unconditional jump over dead code, then a jump that is never taken
+        let program: Vec<i64> = vec![1105,1,5,104,1,1106,1,0,99];
+
+        let graph = ControlFlowGraph::build(&program);
+
+        assert_eq!(graph.successors(0), vec![5]);
+        assert_eq!(graph.successors(5), vec![8]);
+        assert!(graph.block(3).is_none());
+    }
+
+    #[test]
+    fn test_dot() {
+        let dot = ControlFlowGraph::build(&COUNTDOWN).to_dot();
+
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(dot.contains("    b2 [label=\"0002: OUTPUT [13]\\l0004: ADD [13], #-1, [13]\\l0008: JUMPTRUE [13], #2\\l\"];\n"));
+        assert!(dot.contains("    b2 -> b2 [style=solid];\n"));
+        assert!(dot.contains("    b0 -> b2 [style=dashed];\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_day_7_jump_table() {
+        let program: Vec<i64> = parse_program(&fs::read_to_string("input_day_7.txt").unwrap()).unwrap();
+        let static_graph = ControlFlowGraph::build(&program);
+        let mut dynamic_edges = vec![];
+        for phase in 0..10 {
+            let mut computer = IntcodeComputer::new();
+            computer.load(&program);
+            computer.push_input(phase);
+            computer.push_input(0);
+            dynamic_edges.extend(observe_jumps(&mut computer).unwrap());
+        }
+
+        let graph = ControlFlowGraph::build_with(&program, &dynamic_edges);
+
+        // The amplifier picks a routine for its phase through a table in memory
+        assert_eq!(static_graph.unresolved().copied().collect::<Vec<usize>>(), vec![6]);
+        assert_eq!(static_graph.blocks().count(), 1);
+        assert_eq!(graph.successors(0).len(), 10);
+        assert!(graph.edges().filter(|edge| edge.from == 0).all(|edge| edge.kind == EdgeKind::Dynamic));
+        assert!(graph.to_dot().contains("color=blue"));
+    }
+}
@@ -0,0 +1,206 @@
+use crate::disassembler::{disassemble_from_with, Line};
+use crate::registry::OpcodeRegistry;
+use crate::word::Word;
+use crate::{Opcode, Step};
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone, Default)]
+pub struct Coverage {
+    executed: BTreeSet<usize>,
+    read: BTreeSet<usize>,
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone, Copy)]
+pub struct CoverageCount {
+    pub covered: usize,
+    pub total: usize,
+}
+
+impl CoverageCount {
+    pub fn percentage(&self) -> f64 {
+        match self.total {
+            0 => 100.0,
+            total => self.covered as f64 * 100.0 / total as f64,
+        }
+    }
+}
+
+impl fmt::Display for CoverageCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{} ({:.2}%)", self.covered, self.total, self.percentage())
+    }
+}
+
+impl Coverage {
+    pub fn new() -> Coverage {
+        Coverage::default()
+    }
+
+    pub(crate) fn record<W: Word>(&mut self, step: &Step<W>) {
+        self.executed.insert(step.address);
+        self.read.extend(step.reads.iter().map(|&(address, _)| address));
+    }
+
+    // A halt is reached rather than executed, but it still counts as covered
+    pub(crate) fn record_halt(&mut self, address: usize) {
+        self.executed.insert(address);
+    }
+
+    // Combines the coverage of several runs, e.g. one per input
+    pub fn merge(&mut self, other: &Coverage) {
+        self.executed.extend(&other.executed);
+        self.read.extend(&other.read);
+    }
+
+    pub fn executed(&self, address: usize) -> bool {
+        self.executed.contains(&address)
+    }
+
+    pub fn read(&self, address: usize) -> bool {
+        self.read.contains(&address)
+    }
+
+    // Decodes the program from its start and from every executed address, so code no run reached still shows
+    pub fn lines<W: Word>(&self, program: &[W]) -> Vec<Line<W>> {
+        self.lines_with(program, &OpcodeRegistry::new())
+    }
+
+    // Decodes with the opcodes the program ran with, so custom instructions it executed are not shown as data
+    pub fn lines_with<W: Word>(&self, program: &[W], opcodes: &OpcodeRegistry<W>) -> Vec<Line<W>> {
+        let entry_points: Vec<usize> = self.executed.iter().copied().chain(Some(0)).collect();
+        disassemble_from_with(program, &entry_points, opcodes)
+    }
+
+    pub fn instructions<W: Word>(&self, program: &[W]) -> CoverageCount {
+        self.count(&self.lines(program), |_| true)
+    }
+
+    pub fn opcode<W: Word>(&self, program: &[W], opcode: Opcode) -> CoverageCount {
+        self.count(&self.lines(program), |line_opcode| line_opcode == opcode)
+    }
+
+    fn count<W: Word, F: Fn(Opcode) -> bool>(&self, lines: &[Line<W>], include: F) -> CoverageCount {
+        let addresses: Vec<usize> = lines.iter()
+            .filter_map(|line| match line {
+                Line::Instruction { address, opcode, .. } if include(*opcode) => Some(*address),
+                _ => None,
+            })
+            .collect();
+
+        CoverageCount {
+            covered: addresses.iter().filter(|address| self.executed(**address)).count(),
+            total: addresses.len(),
+        }
+    }
+
+    // Marks executed instructions with +, instructions never reached with - and data read as an operand with r
+    pub fn listing<W: Word>(&self, program: &[W]) -> String {
+        self.listing_with(program, &OpcodeRegistry::new())
+    }
+
+    pub fn listing_with<W: Word>(&self, program: &[W], opcodes: &OpcodeRegistry<W>) -> String {
+        let lines = self.lines_with(program, opcodes);
+        let mut listing: String = lines.iter()
+            .map(|line| {
+                let marker = match line {
+                    Line::Instruction { address, .. } if self.executed(*address) => '+',
+                    Line::Instruction { .. } => '-',
+                    Line::Data { address, .. } if self.read(*address) => 'r',
+                    Line::Data { .. } => ' ',
+                };
+                format!("{} {}\n", marker, line)
+            })
+            .collect();
+
+        listing.push_str(&format!("instructions covered: {}\n", self.count(&lines, |_| true)));
+        for opcode in opcodes.opcodes() {
+            let count = self.count(&lines, |line_opcode| line_opcode == opcode);
+            if count.total > 0 {
+                listing.push_str(&format!("  {:<12} {}\n", opcode.mnemonic(), count));
+            }
+        }
+        listing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_program, CustomOpcode, IntcodeComputer};
+    use std::fs;
+
+    fn coverage(program: &[i64], inputs: Vec<i64>) -> Coverage {
+        let mut computer = IntcodeComputer::new();
+        computer.set_coverage(true);
+        computer.load(program);
+        computer.run(Some(inputs)).unwrap();
+        computer.coverage().unwrap().clone()
+    }
+
+    #[test]
+    fn test_listing() {
+        // Outputs 1 for a zero input, otherwise 2
+        let program: Vec<i64> = vec![3,13,1005,13,10,104,1,1105,1,12,104,2,99,0];
+
+        let listing = coverage(&program, vec![0]).listing(&program);
+
+        assert!(listing.starts_with("+ 0000: INPUT [13]\n+ 0002: JUMPTRUE [13], #10\n+ 0005: OUTPUT #1\n"));
+        assert!(listing.contains("- 0010: OUTPUT #2\n+ 0012: HALT\nr 0013: .data 0\n"));
+        assert!(listing.contains("instructions covered: 5/6 (83.33%)\n"));
+        assert!(listing.contains("  OUTPUT       1/2 (50.00%)\n"));
+    }
+
+    #[test]
+    fn test_listing_with_custom_opcodes() {
+        let mut opcodes = OpcodeRegistry::new();
+        opcodes.register(CustomOpcode { code: 20, mnemonic: "TRIPLE", parameter_count: 2, write_parameters: &[1] }, |context| {
+            context.write(context.parameter(0) * 3)
+        }).unwrap();
+        let program: Vec<i64> = vec![120,5,6,4,6,99,0];
+        let mut computer = IntcodeComputer::new();
+        computer.set_coverage(true);
+        computer.set_opcodes(opcodes.clone());
+        computer.load(&program);
+        computer.run(None).unwrap();
+
+        let listing = computer.coverage().unwrap().listing_with(&program, &opcodes);
+
+        assert!(listing.starts_with("+ 0000: TRIPLE #5, [6]\n+ 0003: OUTPUT [6]\n+ 0005: HALT\n"));
+        assert!(listing.contains("  TRIPLE       1/1 (100.00%)\n"));
+    }
+
+    #[test]
+    fn test_merge() {
+        let program: Vec<i64> = vec![3,13,1005,13,10,104,1,1105,1,12,104,2,99,0];
+        let mut merged = coverage(&program, vec![0]);
+
+        merged.merge(&coverage(&program, vec![7]));
+
+        assert!(merged.executed(10));
+        assert_eq!(merged.instructions(&program), CoverageCount { covered: 6, total: 6 });
+    }
+
+    #[test]
+    fn test_day_5_self_tests() {
+        let program = parse_program(&fs::read_to_string("input_day_5.txt").unwrap()).unwrap();
+        let part_1 = coverage(&program, vec![1]);
+        let part_2 = coverage(&program, vec![5]);
+        let mut both = part_1.clone();
+        both.merge(&part_2);
+
+        // Input 1 only runs the add, multiply and I/O self-tests, input 5 only the comparisons and jumps
+        assert_eq!(part_1.opcode(&program, Opcode::Less).covered, 0);
+        assert_eq!(part_1.opcode(&program, Opcode::Equal).covered, 0);
+        assert!(part_2.opcode(&program, Opcode::Less).covered > 0);
+        assert!(part_2.opcode(&program, Opcode::Equal).covered > 0);
+        assert!(both.instructions(&program).covered > part_1.instructions(&program).covered);
+        assert!(both.instructions(&program).covered > part_2.instructions(&program).covered);
+        assert!(both.instructions(&program).percentage() < 100.0);
+    }
+}
@@ -0,0 +1,383 @@
+use crate::limits::RunGuard;
+use crate::word::Word;
+use crate::{IntcodeComputer, Memory, Step, StepOutcome};
+
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+
+const JOURNAL_CAPACITY: usize = 100_000;
+// The most cells one print shows
+const PRINT_LIMIT: usize = 1000;
+
+pub struct Debugger<W: Word = i64> {
+    pub computer: IntcodeComputer<W>,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeSet<usize>,
+}
+
+enum Stop<W: Word> {
+    Stepped,
+    Breakpoint(usize),
+    Watchpoint(usize, W, W),
+    Halted,
+    AwaitingInput,
+    Error(String),
+}
+
+impl<W: Word> Debugger<W> {
+    // Starts a journal on the computer, unless it already keeps one, so that steps can be undone
+    pub fn new(mut computer: IntcodeComputer<W>) -> Debugger<W> {
+        if computer.journal().is_none() {
+            computer.set_journal(Some(JOURNAL_CAPACITY));
+        }
+        Debugger {
+            computer,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+        }
+    }
+
+    pub fn run<R: BufRead, T: Write>(&mut self, input: R, mut output: T) -> io::Result<()> {
+        write!(output, "(debug) ")?;
+        output.flush()?;
+        for line in input.lines() {
+            if !self.execute(line?.trim(), &mut output)? {
+                break;
+            }
+            write!(output, "(debug) ")?;
+            output.flush()?;
+        }
+        writeln!(output)
+    }
+
+    // Returns false once the session should end
+    pub fn execute<T: Write>(&mut self, command: &str, output: &mut T) -> io::Result<bool> {
+        let mut words = command.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Ok(true),
+        };
+        let arguments: Vec<&str> = words.collect();
+
+        match name {
+            "step" | "s" => {
+                let count = match arguments.first() {
+                    Some(count) => match count.parse::<usize>() {
+                        Ok(count) => count,
+                        Err(_) => return usage(output, "step [count]"),
+                    },
+                    None => 1,
+                };
+                for _ in 0..count {
+                    let (step, stop) = self.step();
+                    if let Some(step) = &step {
+                        writeln!(output, "{}", describe(step))?;
+                        print_output(output, step)?;
+                    }
+                    match stop {
+                        Stop::Stepped | Stop::Breakpoint(_) => (),
+                        stop => {
+                            report(output, stop)?;
+                            break;
+                        },
+                    }
+                }
+            },
+            "back" => {
+                let count = match arguments.first() {
+                    Some(count) => match count.parse::<usize>() {
+                        Ok(count) => count,
+                        Err(_) => return usage(output, "back [count]"),
+                    },
+                    None => 1,
+                };
+                for _ in 0..count {
+                    match self.computer.step_back() {
+                        Some(entry) => writeln!(output, "back to {:04}", entry.position)?,
+                        None => {
+                            writeln!(output, "no earlier steps recorded")?;
+                            break;
+                        },
+                    }
+                }
+            },
+            // Only outputs are shown while continuing, and the computer's instruction budget and time limit apply
+            "continue" | "c" => {
+                let mut guard = RunGuard::new(self.computer.instruction_budget, self.computer.time_limit);
+                loop {
+                    if let Err(error) = guard.check::<W>(self.computer.position()) {
+                        report(output, Stop::<W>::Error(error.to_string()))?;
+                        break;
+                    }
+                    let (step, stop) = self.step();
+                    if let Some(step) = &step {
+                        print_output(output, step)?;
+                    }
+                    match stop {
+                        Stop::Stepped => (),
+                        stop => {
+                            report(output, stop)?;
+                            break;
+                        },
+                    }
+                }
+            },
+            "break" | "b" => match parse_addresses(&arguments) {
+                Some(addresses) if !addresses.is_empty() => for address in addresses {
+                    self.breakpoints.insert(address);
+                    writeln!(output, "breakpoint at {:04}", address)?;
+                },
+                _ => return usage(output, "break <address>..."),
+            },
+            "watch" | "w" => match parse_addresses(&arguments) {
+                Some(addresses) if !addresses.is_empty() => for address in addresses {
+                    self.watchpoints.insert(address);
+                    writeln!(output, "watchpoint on mem[{}]", address)?;
+                },
+                _ => return usage(output, "watch <address>..."),
+            },
+            "delete" | "d" => match parse_addresses(&arguments) {
+                Some(addresses) if !addresses.is_empty() => for address in addresses {
+                    self.breakpoints.remove(&address);
+                    self.watchpoints.remove(&address);
+                },
+                _ => return usage(output, "delete <address>..."),
+            },
+            "info" | "i" => {
+                writeln!(output, "breakpoints: {:?}", self.breakpoints)?;
+                writeln!(output, "watchpoints: {:?}", self.watchpoints)?;
+            },
+            "print" | "p" => match arguments.first().and_then(|argument| parse_memory_range(argument)) {
+                Some((start, end)) => {
+                    for address in start..end.min(start.saturating_add(PRINT_LIMIT)) {
+                        writeln!(output, "mem[{}] = {}", address, self.read(address))?;
+                    }
+                    if end - start > PRINT_LIMIT {
+                        writeln!(output, "[{} more cells not shown]", end - start - PRINT_LIMIT)?;
+                    }
+                },
+                None => return usage(output, "print mem[a..b]"),
+            },
+            "regs" | "r" => {
+                writeln!(output, "ip = {}", self.computer.position())?;
+                writeln!(output, "rb = {}", self.computer.relative_base())?;
+            },
+            "input" => {
+                let values: Result<Vec<W>, _> = arguments.iter()
+                    .flat_map(|argument| argument.split(','))
+                    .filter(|value| !value.is_empty())
+                    .map(W::parse_word)
+                    .collect();
+                match values {
+                    Ok(values) if !values.is_empty() => for value in values {
+                        self.computer.push_input(value);
+                    },
+                    _ => return usage(output, "input <value>..."),
+                }
+            },
+            "help" | "h" => {
+                writeln!(output, "step [count], back [count], continue, break <address>, watch <address>, delete <address>, info")?;
+                writeln!(output, "print mem[a..b], regs, input <value>..., quit")?;
+            },
+            "quit" | "q" => return Ok(false),
+            _ => writeln!(output, "unknown command: {}", name)?,
+        }
+        Ok(true)
+    }
+
+    // Runs one instruction, leaving what to print about it to the command
+    fn step(&mut self) -> (Option<Step<W>>, Stop<W>) {
+        let watched: Vec<(usize, W)> = self.watchpoints.iter()
+            .map(|&address| (address, self.read(address)))
+            .collect();
+
+        let step = match self.computer.step() {
+            Ok(StepOutcome::Executed(step)) => step,
+            Ok(StepOutcome::Halted) => return (None, Stop::Halted),
+            Ok(StepOutcome::AwaitingInput) => return (None, Stop::AwaitingInput),
+            Err(error) => return (None, Stop::Error(error.to_string())),
+        };
+
+        let hit = step.writes.iter().find_map(|(address, new_value)| {
+            watched.iter()
+                .find(|(watched_address, _)| watched_address == address)
+                .map(|(_, old_value)| Stop::Watchpoint(*address, old_value.clone(), new_value.clone()))
+        });
+        if let Some(stop) = hit {
+            return (Some(step), stop);
+        }
+        let position = self.computer.position();
+        match self.breakpoints.contains(&position) {
+            true => (Some(step), Stop::Breakpoint(position)),
+            false => (Some(step), Stop::Stepped),
+        }
+    }
+
+    fn read(&self, address: usize) -> W {
+        self.computer.memory.read(address)
+    }
+}
+
+fn describe<W: Word>(step: &Step<W>) -> String {
+    let mut description = format!("{:04}: {:?} {:?}", step.address, step.opcode, step.parameter_positions);
+    for (address, value) in &step.writes {
+        description.push_str(&format!(" mem[{}] <- {}", address, value));
+    }
+    if let Some(target) = step.jump {
+        description.push_str(&format!(" jump {:04}", target));
+    }
+    description
+}
+
+fn print_output<T: Write, W: Word>(output: &mut T, step: &Step<W>) -> io::Result<()> {
+    match &step.output {
+        Some(value) => writeln!(output, "output: {}", value),
+        None => Ok(()),
+    }
+}
+
+fn report<T: Write, W: Word>(output: &mut T, stop: Stop<W>) -> io::Result<()> {
+    match stop {
+        Stop::Stepped => Ok(()),
+        Stop::Breakpoint(address) => writeln!(output, "breakpoint hit at {:04}", address),
+        Stop::Watchpoint(address, old_value, new_value) => writeln!(output, "watchpoint mem[{}]: {} -> {}", address, old_value, new_value),
+        Stop::Halted => writeln!(output, "halted"),
+        Stop::AwaitingInput => writeln!(output, "awaiting input"),
+        Stop::Error(message) => writeln!(output, "error: {}", message),
+    }
+}
+
+fn usage<T: Write>(output: &mut T, usage: &str) -> io::Result<bool> {
+    writeln!(output, "usage: {}", usage)?;
+    Ok(true)
+}
+
+fn parse_addresses(arguments: &[&str]) -> Option<Vec<usize>> {
+    arguments.iter().map(|argument| argument.parse::<usize>().ok()).collect()
+}
+
+// Accepts mem[a], mem[a..b] and mem[a..=b], but not a range that ends past usize::MAX
+fn parse_memory_range(argument: &str) -> Option<(usize, usize)> {
+    let range = argument.strip_prefix("mem[")?.strip_suffix(']')?;
+    match range.split_once("..") {
+        Some((start, end)) => {
+            let start = start.parse::<usize>().ok()?;
+            let end = match end.strip_prefix('=') {
+                Some(end) => end.parse::<usize>().ok()?.checked_add(1)?,
+                None => end.parse::<usize>().ok()?,
+            };
+            Some((start, end.max(start)))
+        },
+        None => {
+            let address = range.parse::<usize>().ok()?;
+            Some((address, address.checked_add(1)?))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn debug(program: Vec<i64>, script: &str) -> String {
+        let mut computer = IntcodeComputer::new();
+        computer.load(&program);
+        let mut debugger = Debugger::new(computer);
+        let mut output: Vec<u8> = Vec::new();
+
+        debugger.run(script.as_bytes(), &mut output).unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_step_and_regs() {
+        let output = debug(vec![109,3,1101,2,3,7,99,0], "step\nregs\nstep\nprint mem[7]\n");
+
+        assert!(output.contains("0000: RelativeBase [1]"));
+        assert!(output.contains("ip = 2"));
+        assert!(output.contains("rb = 3"));
+        assert!(output.contains("0002: Add [3, 4, 7] mem[7] <- 5"));
+        assert!(output.contains("mem[7] = 5"));
+    }
+
+    #[test]
+    fn test_breakpoint() {
+        let output = debug(vec![1101,1,1,0,1101,2,2,0,99], "break 4\ncontinue\nregs\ncontinue\n");
+
+        assert!(output.contains("breakpoint hit at 0004"));
+        assert!(output.contains("ip = 4"));
+        assert!(output.contains("halted"));
+    }
+
+    #[test]
+    fn test_watchpoint() {
+        let output = debug(vec![1101,1,1,9,1101,2,2,10,99,0,0], "watch 10\ncontinue\nprint mem[9..=10]\n");
+
+        assert!(output.contains("watchpoint mem[10]: 0 -> 4"));
+        assert!(output.contains("mem[9] = 2"));
+        assert!(output.contains("mem[10] = 4"));
+    }
+
+    #[test]
+    fn test_interactive_input() {
+        let output = debug(vec![3,5,4,5,99,0], "continue\ninput 17\ncontinue\nquit\nregs\n");
+
+        assert!(output.contains("awaiting input"));
+        assert!(output.contains("output: 17"));
+        assert!(output.contains("halted"));
+        assert!(!output.contains("ip ="));
+    }
+
+    #[test]
+    fn test_back() {
+        let output = debug(vec![1101,1,1,9,1101,2,2,9,99,0], "step 2\nback\nprint mem[9]\nregs\nback 2\n");
+
+        assert!(output.contains("back to 0004"));
+        assert!(output.contains("mem[9] = 2"));
+        assert!(output.contains("ip = 4"));
+        assert!(output.contains("back to 0000"));
+        assert!(output.contains("no earlier steps recorded"));
+    }
+
+    #[test]
+    fn test_continue_shows_only_outputs() {
+        let output = debug(vec![1101,2,3,9,4,9,99,0,0,0], "continue\n");
+
+        assert!(!output.contains("Add"));
+        assert!(output.contains("output: 5\nhalted"));
+    }
+
+    #[test]
+    fn test_continue_honours_budget() {
+        let mut computer = IntcodeComputer::new();
+        computer.set_instruction_budget(Some(100));
+        computer.load(&[1105,1,0]);
+        let mut output: Vec<u8> = Vec::new();
+
+        Debugger::new(computer).run("continue\nregs\n".as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("error: "));
+        assert!(output.contains("ip = 0"));
+    }
+
+    #[test]
+    fn test_print_limits() {
+        let output = debug(vec![99], "print mem[18446744073709551615]\nprint mem[5..=18446744073709551615]\nprint mem[0..1000000000000]\n");
+
+        assert_eq!(output.matches("usage: print mem[a..b]").count(), 2);
+        assert!(output.contains("mem[999] = 0\n[999999999000 more cells not shown]\n"));
+        assert!(!output.contains("mem[1000] ="));
+        assert_eq!(parse_memory_range("mem[18446744073709551614]"), Some((usize::MAX - 1, usize::MAX)));
+    }
+
+    #[test]
+    fn test_errors_are_reported() {
+        let output = debug(vec![42], "step\nfrobnicate\nprint mem\n");
+
+        assert!(output.contains("error: Opcode not recognised: 42 at address 0"));
+        assert!(output.contains("unknown command: frobnicate"));
+        assert!(output.contains("usage: print mem[a..b]"));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::registry::OpcodeRegistry;
+use crate::word::Word;
+use crate::{IntcodeComputer, IntcodeError, Opcode, ParameterMode, StepOutcome};
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub struct Operand<W: Word = i64> {
+    pub mode: ParameterMode,
+    pub value: W,
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub enum Line<W: Word = i64> {
+    Instruction { address: usize, opcode: Opcode, operands: Vec<Operand<W>> },
+    Data { address: usize, value: W },
+}
+
+impl<W: Word> Line<W> {
+    pub fn address(&self) -> usize {
+        match self {
+            Line::Instruction { address, .. } | Line::Data { address, .. } => *address,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Line::Instruction { operands, .. } => operands.len() + 1,
+            Line::Data { .. } => 1,
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for Operand<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.value.to_string();
+        match (self.mode, value.strip_prefix('-')) {
+            (ParameterMode::PositionalMode, _) => write!(f, "[{}]", value),
+            (ParameterMode::ImmediateMode, _) => write!(f, "#{}", value),
+            (ParameterMode::RelativeMode, Some(magnitude)) => write!(f, "[rb-{}]", magnitude),
+            (ParameterMode::RelativeMode, None) => write!(f, "[rb+{}]", value),
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for Line<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Line::Instruction { address, opcode, operands } => {
+                write!(f, "{:04}: {}", address, opcode.mnemonic())?;
+                for (index, operand) in operands.iter().enumerate() {
+                    write!(f, "{}{}", if index == 0 { " " } else { ", " }, operand)?;
+                }
+                Ok(())
+            },
+            Line::Data { address, value } => write!(f, "{:04}: .data {}", address, value),
+        }
+    }
+}
+
+pub fn decode_at<W: Word>(program: &[W], address: usize) -> Option<Line<W>> {
+    decode_at_with(program, address, &OpcodeRegistry::new())
+}
+
+// Decodes with the opcodes a computer was given, so custom instructions are not mistaken for data
+pub fn decode_at_with<W: Word>(program: &[W], address: usize, opcodes: &OpcodeRegistry<W>) -> Option<Line<W>> {
+    let (opcode, parameter_modes) = opcodes.decode(program.get(address)?, address).ok()?;
+    if address + opcode.parameter_count() >= program.len() {
+        return None;
+    }
+
+    let operands = parameter_modes.iter().take(opcode.parameter_count()).enumerate()
+        .map(|(index, &mode)| Operand { mode, value: program[address + index + 1].clone() })
+        .collect();
+    Some(Line::Instruction { address, opcode, operands })
+}
+
+// Decodes every address in turn, falling back to data wherever no valid instruction starts
+pub fn disassemble<W: Word>(program: &[W]) -> Vec<Line<W>> {
+    disassemble_with(program, &OpcodeRegistry::new())
+}
+
+pub fn disassemble_with<W: Word>(program: &[W], opcodes: &OpcodeRegistry<W>) -> Vec<Line<W>> {
+    let mut lines = vec![];
+    let mut address = 0;
+
+    while address < program.len() {
+        let line = decode_at_with(program, address, opcodes).unwrap_or(Line::Data { address, value: program[address].clone() });
+        address += line.size();
+        lines.push(line);
+    }
+
+    lines
+}
+
+// Only decodes instructions reachable from the entry points by fallthrough or immediate jump targets
+pub fn disassemble_from<W: Word>(program: &[W], entry_points: &[usize]) -> Vec<Line<W>> {
+    disassemble_from_with(program, entry_points, &OpcodeRegistry::new())
+}
+
+pub fn disassemble_from_with<W: Word>(program: &[W], entry_points: &[usize], opcodes: &OpcodeRegistry<W>) -> Vec<Line<W>> {
+    let mut instructions: BTreeMap<usize, Line<W>> = BTreeMap::new();
+    let mut covered = vec![false; program.len()];
+    let mut pending: Vec<usize> = entry_points.iter().rev().copied().collect();
+
+    while let Some(address) = pending.pop() {
+        let line = match decode_at_with(program, address, opcodes) {
+            Some(line) => line,
+            None => continue,
+        };
+        if covered[address..address + line.size()].iter().any(|&covered| covered) {
+            continue;
+        }
+        covered[address..address + line.size()].iter_mut().for_each(|covered| *covered = true);
+
+        if let Line::Instruction { opcode, operands, .. } = &line {
+            if *opcode != Opcode::Halt && jump_condition(*opcode, operands) != Some(true) {
+                pending.push(address + line.size());
+            }
+            if let Some(target) = jump_target(*opcode, operands) {
+                pending.push(target);
+            }
+        }
+        instructions.insert(address, line);
+    }
+
+    let mut lines = vec![];
+    let mut address = 0;
+    while address < program.len() {
+        let line = instructions.remove(&address).unwrap_or(Line::Data { address, value: program[address].clone() });
+        address += line.size();
+        lines.push(line);
+    }
+
+    lines
+}
+
+// Runs the program and returns the instruction addresses and jump targets it visited
+pub fn trace_entry_points<W: Word>(computer: &mut IntcodeComputer<W>) -> Result<Vec<usize>, IntcodeError<W>> {
+    let mut entry_points = vec![computer.position()];
+
+    while let StepOutcome::Executed(step) = computer.step()? {
+        entry_points.push(step.address);
+        entry_points.extend(step.jump);
+    }
+    entry_points.push(computer.position());
+    entry_points.sort_unstable();
+    entry_points.dedup();
+
+    Ok(entry_points)
+}
+
+pub fn listing<W: Word>(lines: &[Line<W>]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+// Whether a jump is always or never taken, when its condition is an immediate value
+pub(crate) fn jump_condition<W: Word>(opcode: Opcode, operands: &[Operand<W>]) -> Option<bool> {
+    match opcode {
+        Opcode::JumpTrue | Opcode::JumpFalse if operands[0].mode == ParameterMode::ImmediateMode => {
+            Some((opcode == Opcode::JumpTrue) != operands[0].value.is_zero())
+        },
+        _ => None,
+    }
+}
+
+// The target of a jump given as an immediate value
+pub(crate) fn jump_target<W: Word>(opcode: Opcode, operands: &[Operand<W>]) -> Option<usize> {
+    match opcode {
+        Opcode::JumpTrue | Opcode::JumpFalse if operands[1].mode == ParameterMode::ImmediateMode => operands[1].value.to_usize(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_program, CustomOpcode};
+    use std::fs;
+
+    #[test]
+    fn test_operand_formatting() {
+        let line: Line = Line::Instruction {
+            address: 10,
+            opcode: Opcode::Add,
+            operands: vec![
+                Operand { mode: ParameterMode::RelativeMode, value: 3 },
+                Operand { mode: ParameterMode::ImmediateMode, value: 5 },
+                Operand { mode: ParameterMode::PositionalMode, value: 12 },
+            ],
+        };
+
+        assert_eq!(line.to_string(), "0010: ADD [rb+3], #5, [12]");
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let program: Vec<i64> = vec![21101,3,-4,-1,204,-2,99,12345,1];
+
+        assert_eq!(listing(&disassemble(&program)), "\
+0000: ADD #3, #-4, [rb-1]
+0004: OUTPUT [rb-2]
+0006: HALT
+0007: .data 12345
+0008: .data 1
+");
+    }
+
+    #[test]
+    fn test_truncated_instruction_is_data() {
+        let program: Vec<i64> = vec![99,1,0,0];
+
+        assert_eq!(listing(&disassemble(&program)), "0000: HALT\n0001: .data 1\n0002: .data 0\n0003: .data 0\n");
+    }
+
+    #[test]
+    fn test_custom_opcodes() {
+        let mut opcodes = OpcodeRegistry::new();
+        opcodes.register(CustomOpcode { code: 20, mnemonic: "TRIPLE", parameter_count: 2, write_parameters: &[1] }, |_| Ok(())).unwrap();
+        let program: Vec<i64> = vec![120,5,6,4,6,99,0];
+
+        assert_eq!(listing(&disassemble_with(&program, &opcodes)), "\
+0000: TRIPLE #5, [6]
+0003: OUTPUT [6]
+0005: HALT
+0006: .data 0
+");
+        assert_eq!(disassemble(&program)[0].to_string(), "0000: .data 120");
+    }
+
+    #[test]
+    fn test_disassemble_from_follows_jumps() {
+        let program: Vec<i64> = vec![1105,1,5,1,1,99,104,7,99];
+
+        assert_eq!(listing(&disassemble_from(&program, &[0])), "\
+0000: JUMPTRUE #1, #5
+0003: .data 1
+0004: .data 1
+0005: HALT
+0006: .data 104
+0007: .data 7
+0008: .data 99
+");
+    }
+
+    #[test]
+    fn test_disassemble_from_trace() {
+        let program: Vec<i64> = vec![3,11,1005,11,7,99,0,104,1,99,0,0];
+        let mut computer = IntcodeComputer::new();
+        computer.load(&program);
+        computer.push_input(1);
+
+        let entry_points = trace_entry_points(&mut computer).unwrap();
+        let lines = disassemble_from(&program, &entry_points);
+
+        assert_eq!(entry_points, vec![0,2,7,9]);
+        assert_eq!(lines[2].to_string(), "0005: HALT");
+        assert_eq!(lines[3].to_string(), "0006: .data 0");
+        assert_eq!(lines[4].to_string(), "0007: OUTPUT #1");
+    }
+
+    #[test]
+    fn test_disassemble_day_5() {
+        let program: Vec<i64> = parse_program(&fs::read_to_string("input_day_5.txt").unwrap()).unwrap();
+
+        let lines = disassemble(&program);
+
+        assert_eq!(lines[0].to_string(), "0000: INPUT [225]");
+        assert_eq!(lines[1].to_string(), "0002: ADD [225], [6], [6]");
+        assert_eq!(lines.iter().map(Line::size).sum::<usize>(), program.len());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::word::Word;
+
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum IntcodeError<W: Word = i64> {
+    UnknownOpcode { opcode: W, address: usize },
+    InvalidParameterMode { mode: usize, address: usize },
+    NegativeAddress { address: W, position: usize },
+    AddressOutOfRange { address: W, position: usize },
+    WriteToImmediate { address: usize },
+    MissingInput { address: usize },
+    MemoryLimitExceeded { address: usize, limit: usize },
+    InstructionBudgetExhausted { budget: usize, address: usize },
+    TimeLimitExceeded { limit: Duration, address: usize },
+    InfiniteLoop { address: usize, length: usize },
+    ArithmeticOverflow { address: usize },
+    // A custom instruction tried to write without its opcode having a write parameter
+    NoWriteParameter { opcode: W, address: usize },
+}
+
+impl<W: Word> fmt::Display for IntcodeError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::UnknownOpcode { opcode, address } => write!(f, "Opcode not recognised: {} at address {}", opcode, address),
+            IntcodeError::InvalidParameterMode { mode, address } => write!(f, "ParameterMode not implemented: {} at address {}", mode, address),
+            IntcodeError::NegativeAddress { address, position } => write!(f, "Negative address {} referenced by instruction at address {}", address, position),
+            IntcodeError::AddressOutOfRange { address, position } => write!(f, "Address {} referenced by instruction at address {} is out of range", address, position),
+            IntcodeError::WriteToImmediate { address } => write!(f, "Write to immediate mode parameter by instruction at address {}", address),
+            IntcodeError::MissingInput { address } => write!(f, "No input available for instruction at address {}", address),
+            IntcodeError::MemoryLimitExceeded { address, limit } => write!(f, "Writing to address {} would exceed the memory limit of {} cells", address, limit),
+            IntcodeError::InstructionBudgetExhausted { budget, address } => write!(f, "Instruction budget of {} used up before address {}", budget, address),
+            IntcodeError::TimeLimitExceeded { limit, address } => write!(f, "Time limit of {:?} exceeded before address {}", limit, address),
+            IntcodeError::InfiniteLoop { address, length } => write!(f, "Infinite loop of {} instructions detected at address {}", length, address),
+            IntcodeError::ArithmeticOverflow { address } => write!(f, "Arithmetic overflow in instruction at address {}", address),
+            IntcodeError::NoWriteParameter { opcode, address } => write!(f, "Opcode {} at address {} has no parameter to write to", opcode, address),
+        }
+    }
+}
+
+impl<W: Word> Error for IntcodeError<W> {}
+
+// Turns a word used as an address into an index into memory
+pub(crate) fn address<W: Word>(value: &W, position: usize) -> Result<usize, IntcodeError<W>> {
+    match value.to_usize() {
+        Some(address) => Ok(address),
+        None if value.is_negative() => Err(IntcodeError::NegativeAddress { address: value.clone(), position }),
+        None => Err(IntcodeError::AddressOutOfRange { address: value.clone(), position }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BigInt;
+
+    #[test]
+    fn test_display() {
+        let error: IntcodeError = IntcodeError::UnknownOpcode { opcode: 42, address: 7 };
+
+        assert_eq!(error.to_string(), "Opcode not recognised: 42 at address 7");
+    }
+
+    #[test]
+    fn test_address() {
+        let huge = BigInt::from(i128::MAX);
+
+        assert_eq!(address(&12i64, 3), Ok(12));
+        assert_eq!(address(&-1i64, 3), Err(IntcodeError::NegativeAddress { address: -1, position: 3 }));
+        assert_eq!(address(&huge, 3), Err(IntcodeError::AddressOutOfRange { address: huge.clone(), position: 3 }));
+    }
+}
@@ -0,0 +1,451 @@
+use crate::{IntcodeComputer, IntcodeError, RunState};
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+// Generated programs can loop forever, so every run stops after this many instructions
+pub const MAX_STEPS: usize = 1000;
+const PROGRAM_LENGTH: usize = 40;
+
+// (opcode, parameter count, write parameter)
+const OPCODES: [(i64, usize, Option<usize>); 9] = [
+    (1, 3, Some(2)),
+    (2, 3, Some(2)),
+    (3, 1, Some(0)),
+    (4, 1, None),
+    (5, 2, None),
+    (6, 2, None),
+    (7, 3, Some(2)),
+    (8, 3, Some(2)),
+    (9, 1, None),
+];
+
+pub type Configure = fn(&mut IntcodeComputer);
+
+// Each optimisation takes a different path through the computer, so each is compared on its own
+pub const CONFIGURATIONS: [(&str, Configure); 3] = [
+    ("plain", plain),
+    ("decode cache", decode_cache),
+    ("journal", journal),
+];
+
+fn plain(_: &mut IntcodeComputer) {}
+
+fn decode_cache(computer: &mut IntcodeComputer) {
+    computer.set_decode_cache(true);
+}
+
+fn journal(computer: &mut IntcodeComputer) {
+    computer.set_journal(Some(16));
+}
+
+// xorshift64*, so a seed always generates the same programs
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1 }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    // Inclusive of both ends
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        low + self.below((high - low + 1) as usize) as i64
+    }
+}
+
+// Valid opcodes and modes, with addresses kept near the program and immediate jumps aimed at instructions.
+// Occasional huge immediates exercise overflow, and runs end in a halt or on data.
+pub fn generate_program(rng: &mut Rng, length: usize) -> Vec<i64> {
+    let mut program = vec![];
+    let mut starts = vec![];
+    let mut jump_targets = vec![];
+    while program.len() < length {
+        starts.push(program.len());
+        if rng.below(20) == 0 {
+            program.push(99);
+            continue;
+        }
+        let (opcode, parameter_count, write_parameter) = OPCODES[rng.below(OPCODES.len())];
+        let mut code = opcode;
+        let mut operands = vec![];
+        for index in 0..parameter_count {
+            let mode = match write_parameter == Some(index) {
+                true => [0, 2][rng.below(2)],
+                false => rng.below(3),
+            };
+            code += mode as i64 * 10i64.pow(index as u32 + 2);
+            if (opcode == 5 || opcode == 6) && index == 1 && mode == 1 {
+                jump_targets.push(program.len() + index + 1);
+            }
+            operands.push(match mode {
+                0 => rng.below(length + 8) as i64,
+                2 if rng.below(8) == 0 => rng.range(-4, -1),
+                2 => rng.range(0, 12),
+                _ if rng.below(16) == 0 => i64::MAX - rng.range(0, 3),
+                _ => rng.range(-10, 10),
+            });
+        }
+        program.push(code);
+        program.extend(operands);
+    }
+    for operand in jump_targets {
+        program[operand] = starts[rng.below(starts.len())] as i64;
+    }
+    program.push(99);
+    program.extend((0..rng.below(4)).map(|_| rng.range(-10, 10)));
+    program
+}
+
+pub fn generate_inputs(rng: &mut Rng) -> Vec<i64> {
+    (0..rng.below(5)).map(|_| rng.range(-10, 10)).collect()
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone, Copy)]
+pub enum Stop {
+    Halted,
+    AwaitingInput,
+    // Any error, which both sides only need to agree happened at the same instruction
+    Fault,
+    OutOfSteps,
+}
+
+// Memory holds only non-zero cells, since a zero cell and one never written read the same
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub struct Outcome {
+    pub stop: Stop,
+    pub outputs: Vec<i64>,
+    pub memory: BTreeMap<usize, i64>,
+    pub position: usize,
+    pub relative_base: usize,
+}
+
+// Kept as plain as possible, so that it is easy to convince yourself it is right
+struct Reference {
+    memory: BTreeMap<usize, i64>,
+    position: usize,
+    relative_base: usize,
+    inputs: VecDeque<i64>,
+    outputs: Vec<i64>,
+}
+
+impl Reference {
+    fn read(&self, address: usize) -> i64 {
+        self.memory.get(&address).copied().unwrap_or(0)
+    }
+
+    fn address(value: i64) -> Result<usize, Stop> {
+        match value >= 0 {
+            true => Ok(value as usize),
+            false => Err(Stop::Fault),
+        }
+    }
+
+    // Runs one instruction, or says why it cannot
+    fn step(&mut self, steps: usize) -> Result<(), Stop> {
+        let code = self.read(self.position);
+        if code < 0 {
+            return Err(Stop::Fault);
+        }
+        let (parameter_count, write_parameter) = match code % 100 {
+            99 => (0, None),
+            opcode => OPCODES.iter().find(|(known, _, _)| *known == opcode).map(|&(_, count, write)| (count, write)).ok_or(Stop::Fault)?,
+        };
+        let mut addresses = vec![];
+        let mut modes = code / 100;
+        for index in 0..parameter_count {
+            let value = self.read(self.position + index + 1);
+            let address = match modes % 10 {
+                0 => Reference::address(value)?,
+                1 if write_parameter == Some(index) => return Err(Stop::Fault),
+                1 => self.position + index + 1,
+                2 => Reference::address((self.relative_base as i64).checked_add(value).ok_or(Stop::Fault)?)?,
+                _ => return Err(Stop::Fault),
+            };
+            addresses.push(address);
+            modes /= 10;
+        }
+        if code % 100 == 99 {
+            return Err(Stop::Halted);
+        }
+        if code % 100 == 3 && self.inputs.is_empty() {
+            return Err(Stop::AwaitingInput);
+        }
+        if steps == MAX_STEPS {
+            return Err(Stop::OutOfSteps);
+        }
+
+        let values: Vec<i64> = addresses.iter().map(|address| self.read(*address)).collect();
+        let parameter = |index: usize| values[index];
+        let (write, next) = match code % 100 {
+            1 => (Some(parameter(0).checked_add(parameter(1)).ok_or(Stop::Fault)?), self.position + 4),
+            2 => (Some(parameter(0).checked_mul(parameter(1)).ok_or(Stop::Fault)?), self.position + 4),
+            3 => (self.inputs.pop_front(), self.position + 2),
+            4 => {
+                self.outputs.push(parameter(0));
+                (None, self.position + 2)
+            },
+            5 if parameter(0) != 0 => (None, Reference::address(parameter(1))?),
+            6 if parameter(0) == 0 => (None, Reference::address(parameter(1))?),
+            5 | 6 => (None, self.position + 3),
+            7 => (Some((parameter(0) < parameter(1)) as i64), self.position + 4),
+            8 => (Some((parameter(0) == parameter(1)) as i64), self.position + 4),
+            _ => {
+                let base = (self.relative_base as i64).checked_add(parameter(0)).ok_or(Stop::Fault)?;
+                self.relative_base = Reference::address(base)?;
+                (None, self.position + 2)
+            },
+        };
+        if let (Some(value), Some(index)) = (write, write_parameter) {
+            self.memory.insert(addresses[index], value);
+        }
+        self.position = next;
+        Ok(())
+    }
+}
+
+pub fn run_reference(program: &[i64], inputs: &[i64]) -> Outcome {
+    let mut reference = Reference {
+        memory: program.iter().copied().enumerate().collect(),
+        position: 0,
+        relative_base: 0,
+        inputs: inputs.iter().copied().collect(),
+        outputs: vec![],
+    };
+    let mut steps = 0;
+    let stop = loop {
+        match reference.step(steps) {
+            Ok(()) => steps += 1,
+            Err(stop) => break stop,
+        }
+    };
+    reference.memory.retain(|_, value| *value != 0);
+
+    Outcome {
+        stop,
+        outputs: reference.outputs,
+        memory: reference.memory,
+        position: reference.position,
+        relative_base: reference.relative_base,
+    }
+}
+
+pub fn run_computer(program: &[i64], inputs: &[i64], configure: Configure) -> Outcome {
+    let mut computer = IntcodeComputer::new();
+    configure(&mut computer);
+    computer.set_instruction_budget(Some(MAX_STEPS));
+    computer.load(program);
+    inputs.iter().for_each(|input| computer.push_input(*input));
+    let mut outputs = vec![];
+    let stop = match computer.run_with(&mut || None, &mut outputs) {
+        Ok(RunState::AwaitingInput) => Stop::AwaitingInput,
+        Ok(_) => Stop::Halted,
+        Err(IntcodeError::InstructionBudgetExhausted { .. }) => Stop::OutOfSteps,
+        Err(_) => Stop::Fault,
+    };
+    let memory = computer.memory.image().iter().copied().enumerate()
+        .chain(computer.memory.sparse_cells())
+        .filter(|(_, value)| *value != 0)
+        .collect();
+
+    Outcome {
+        stop,
+        outputs,
+        memory,
+        position: computer.position(),
+        relative_base: computer.relative_base(),
+    }
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Failure {
+    pub configuration: &'static str,
+    pub program: Vec<i64>,
+    pub inputs: Vec<i64>,
+    pub expected: Outcome,
+    pub actual: Outcome,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "configuration: {}", self.configuration)?;
+        writeln!(f, "program: {}", join(&self.program))?;
+        writeln!(f, "inputs: {}", join(&self.inputs))?;
+        writeln!(f, "expected: {:?}", self.expected)?;
+        write!(f, "actual: {:?}", self.actual)
+    }
+}
+
+fn join(values: &[i64]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(",")
+}
+
+pub fn compare(program: &[i64], inputs: &[i64], (name, configure): (&'static str, Configure)) -> Result<(), Box<Failure>> {
+    let expected = run_reference(program, inputs);
+    let actual = run_computer(program, inputs, configure);
+    match expected == actual {
+        true => Ok(()),
+        false => Err(Box::new(Failure { configuration: name, program: program.to_vec(), inputs: inputs.to_vec(), expected, actual })),
+    }
+}
+
+// Runs the given number of generated cases under each configuration, shrinking the first disagreement found
+pub fn fuzz(seed: u64, cases: usize, configurations: &[(&'static str, Configure)]) -> Result<(), Box<Failure>> {
+    let mut rng = Rng::new(seed);
+    for _ in 0..cases {
+        let program = generate_program(&mut rng, PROGRAM_LENGTH);
+        let inputs = generate_inputs(&mut rng);
+        for &configuration in configurations {
+            if let Err(failure) = compare(&program, &inputs, configuration) {
+                return Err(shrink(failure, configuration));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn shrink(failure: Box<Failure>, configuration: (&'static str, Configure)) -> Box<Failure> {
+    let (program, inputs) = shrink_with(&failure.program, &failure.inputs, |program, inputs| compare(program, inputs, configuration).is_err());
+    compare(&program, &inputs, configuration).err().unwrap_or(failure)
+}
+
+// Greedily removes cells and inputs and moves values towards zero for as long as the case keeps failing.
+// Every change accepted makes the case shorter or its values smaller, so this always finishes.
+pub fn shrink_with<F: Fn(&[i64], &[i64]) -> bool>(program: &[i64], inputs: &[i64], fails: F) -> (Vec<i64>, Vec<i64>) {
+    let (mut program, mut inputs) = (program.to_vec(), inputs.to_vec());
+    let mut progress = true;
+    while progress {
+        progress = false;
+        for size in (1..=4).rev() {
+            let mut start = 0;
+            while start + size <= program.len() {
+                let candidate: Vec<i64> = program[..start].iter().chain(&program[start + size..]).copied().collect();
+                match fails(&candidate, &inputs) {
+                    true => {
+                        program = candidate;
+                        progress = true;
+                    },
+                    false => start += 1,
+                }
+            }
+        }
+        let mut index = 0;
+        while index < inputs.len() {
+            let mut candidate = inputs.clone();
+            candidate.remove(index);
+            match fails(&program, &candidate) {
+                true => {
+                    inputs = candidate;
+                    progress = true;
+                },
+                false => index += 1,
+            }
+        }
+        for index in 0..program.len() {
+            for simpler in simpler_values(program[index]) {
+                let mut candidate = program.clone();
+                candidate[index] = simpler;
+                if fails(&candidate, &inputs) {
+                    program = candidate;
+                    progress = true;
+                    break;
+                }
+            }
+        }
+    }
+    (program, inputs)
+}
+
+fn simpler_values(value: i64) -> Vec<i64> {
+    let mut values = vec![0, value / 2];
+    if value < 0 {
+        values.push(-value / 2);
+    }
+    values.retain(|simpler| simpler.unsigned_abs() < value.unsigned_abs());
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OpcodeRegistry, UnknownOpcodeAction};
+
+    #[test]
+    fn test_rng_is_deterministic() {
+        let mut first = Rng::new(7);
+        let mut second = Rng::new(7);
+
+        assert_eq!((0..5).map(|_| first.next_u64()).collect::<Vec<u64>>(), (0..5).map(|_| second.next_u64()).collect::<Vec<u64>>());
+        assert!((0..100).map(|_| first.range(-3, 3)).all(|value| (-3..=3).contains(&value)));
+    }
+
+    #[test]
+    fn test_reference() {
+        let day_9_quine = vec![109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99];
+
+        let quine = run_reference(&day_9_quine, &[]);
+        let waiting = run_reference(&[3,5,4,5,99,0], &[]);
+        let looping = run_reference(&[1105,1,0], &[]);
+        let overflow = run_reference(&[1102,i64::MAX,2,0,99], &[]);
+
+        assert_eq!((quine.stop, quine.outputs), (Stop::Halted, day_9_quine));
+        assert_eq!((waiting.stop, waiting.position), (Stop::AwaitingInput, 0));
+        assert_eq!((looping.stop, looping.position), (Stop::OutOfSteps, 0));
+        assert_eq!((overflow.stop, overflow.memory.get(&0)), (Stop::Fault, Some(&1102)));
+    }
+
+    #[test]
+    fn test_computer_matches_reference() {
+        for seed in 0..4 {
+            if let Err(failure) = fuzz(seed, 250, &CONFIGURATIONS) {
+                panic!("{}", failure);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shrink_with() {
+        let program = vec![1101,3,4,20,1002,20,2,20,4,20,99,5,6,7];
+
+        // Fails whenever the reference outputs an even number
+        let (shrunk, inputs) = shrink_with(&program, &[1,2], |program, inputs| run_reference(program, inputs).outputs.iter().any(|output| output % 2 == 0));
+
+        assert_eq!((shrunk, inputs), (vec![4], vec![]));
+    }
+
+    #[test]
+    fn test_finds_and_shrinks_a_broken_opcode() {
+        // Less than removed, with the unknown opcode handler stepping over it as if it took no parameters
+        fn broken_less(computer: &mut IntcodeComputer) {
+            let mut opcodes = OpcodeRegistry::new();
+            opcodes.remove(7);
+            opcodes.set_unknown_handler(|_, _| UnknownOpcodeAction::Skip);
+            computer.set_opcodes(opcodes);
+        }
+
+        let failure = fuzz(1, 250, &[("broken less", broken_less)]).unwrap_err();
+
+        assert_eq!(failure.configuration, "broken less");
+        assert!(failure.program.len() <= 4, "{}", failure);
+        assert!(compare(&failure.program, &failure.inputs, ("broken less", broken_less)).is_err());
+    }
+}
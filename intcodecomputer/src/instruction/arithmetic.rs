@@ -0,0 +1,60 @@
+use crate::error::IntcodeError;
+use crate::word::Word;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone, Copy, Default)]
+pub enum Arithmetic {
+    // Overflow stops the computer with an error naming the instruction's address
+    #[default]
+    Checked,
+    // Results wrap around at the bounds of the word, as two's complement hardware would
+    Wrapping,
+}
+
+// There is no wide policy: for results past i64, run an IntcodeComputer<i128> or IntcodeComputer<BigInt> instead
+
+impl Arithmetic {
+    pub fn add<W: Word>(&self, left: &W, right: &W, address: usize) -> Result<W, IntcodeError<W>> {
+        match self {
+            Arithmetic::Checked => left.add_checked(right).ok_or(IntcodeError::ArithmeticOverflow { address }),
+            Arithmetic::Wrapping => Ok(left.add_wrapping(right)),
+        }
+    }
+
+    pub fn multiply<W: Word>(&self, left: &W, right: &W, address: usize) -> Result<W, IntcodeError<W>> {
+        match self {
+            Arithmetic::Checked => left.multiply_checked(right).ok_or(IntcodeError::ArithmeticOverflow { address }),
+            Arithmetic::Wrapping => Ok(left.multiply_wrapping(right)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BigInt;
+
+    #[test]
+    fn test_checked() {
+        assert_eq!(Arithmetic::Checked.add(&2i64, &3, 0), Ok(5));
+        assert_eq!(Arithmetic::Checked.multiply(&-4i64, &3, 0), Ok(-12));
+        assert_eq!(Arithmetic::Checked.add(&i64::MAX, &1, 8), Err(IntcodeError::ArithmeticOverflow { address: 8 }));
+        assert_eq!(Arithmetic::Checked.multiply(&i64::MIN, &-1, 4), Err(IntcodeError::ArithmeticOverflow { address: 4 }));
+    }
+
+    #[test]
+    fn test_wrapping() {
+        assert_eq!(Arithmetic::Wrapping.add(&i64::MAX, &1, 0), Ok(i64::MIN));
+        assert_eq!(Arithmetic::Wrapping.multiply(&i64::MAX, &2, 0), Ok(-2));
+    }
+
+    #[test]
+    fn test_wide_words() {
+        let wide = Arithmetic::Checked.multiply(&(i64::MAX as i128), &4, 0);
+        let big = Arithmetic::Wrapping.multiply(&BigInt::from(i128::MAX), &BigInt::from(2i64), 0);
+
+        assert_eq!(wide, Ok(i64::MAX as i128 * 4));
+        assert_eq!(big.unwrap().to_string(), "340282366920938463463374607431768211454");
+    }
+}
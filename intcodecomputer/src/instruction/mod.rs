@@ -1,129 +1,173 @@
+pub mod arithmetic;
 pub mod opcode;
 pub mod parametermode;
+use arithmetic::Arithmetic;
 use opcode::Opcode;
 use parametermode::ParameterMode;
 
-use std::convert::TryFrom;
+use crate::error::{self, IntcodeError};
+use crate::memory::Memory;
+use crate::word::Word;
+
+pub const MAX_PARAMETERS: usize = 3;
 
 #[derive(Debug)]
 #[derive(PartialEq)]
+#[derive(Clone, Copy)]
 pub struct Instruction {
     pub opcode: Opcode,
-    parameter_positions: Option<Vec<usize>>,
-    parameter_modes: Vec<ParameterMode>,
+    parameter_positions: [usize; MAX_PARAMETERS],
+    parameter_modes: [ParameterMode; MAX_PARAMETERS],
 }
 
 impl Instruction {
-    pub fn from(memory: &Vec<isize>, position: usize, relative_base: usize) -> Instruction {
-        let instruction_code = usize::try_from(memory[position]).unwrap();
-        let mut parameter_modes: Vec<ParameterMode> = Vec::new();
+    pub fn from<W: Word, M: Memory<W> + ?Sized>(memory: &M, position: usize, relative_base: usize) -> Result<Instruction, IntcodeError<W>> {
+        let (opcode, parameter_modes) = Instruction::decode(&memory.read(position), position)?;
+
+        Instruction::resolve(memory, position, relative_base, opcode, parameter_modes)
+    }
+
+    // Splits an instruction code found at the given address into its opcode and parameter modes
+    pub fn decode<W: Word>(raw_code: &W, address: usize) -> Result<(Opcode, [ParameterMode; MAX_PARAMETERS]), IntcodeError<W>> {
+        Instruction::decode_with(raw_code, address, Opcode::from)
+    }
+
+    // Like decode, with the opcodes looked up by their two digit code
+    pub fn decode_with<W: Word, F: Fn(usize) -> Option<Opcode>>(raw_code: &W, address: usize, opcodes: F) -> Result<(Opcode, [ParameterMode; MAX_PARAMETERS]), IntcodeError<W>> {
+        let unknown = || IntcodeError::UnknownOpcode { opcode: raw_code.clone(), address };
+        let instruction_code = raw_code.to_usize().ok_or_else(unknown)?;
+        let mut parameter_modes = [ParameterMode::PositionalMode; MAX_PARAMETERS];
 
-        let opcode = Opcode::from(instruction_code % 100);
+        let opcode = opcodes(instruction_code % 100).ok_or_else(unknown)?;
 
-        for x in 1..=(opcode.parameter_count()) {
-            // Get the digit corrosponding to the parameter mode
-            let mode_integer = (instruction_code / 10) / (10_usize.pow(u32::try_from(x).unwrap())) % 10;
-            parameter_modes.push(ParameterMode::from(mode_integer));
+        // Each digit above the opcode holds the mode of the next parameter
+        let mut mode_digits = instruction_code / 100;
+        for parameter_mode in parameter_modes.iter_mut().take(opcode.parameter_count()) {
+            let mode_integer = mode_digits % 10;
+            *parameter_mode = ParameterMode::from(mode_integer).ok_or(IntcodeError::InvalidParameterMode { mode: mode_integer, address })?;
+            mode_digits /= 10;
+        }
+
+        if opcode.write_parameters().iter().any(|&index| parameter_modes[index] == ParameterMode::ImmediateMode) {
+            return Err(IntcodeError::WriteToImmediate { address });
         }
-        
-        let mut instruction = Instruction {
-            opcode: opcode,
-            parameter_positions: None,
-            parameter_modes: parameter_modes,
-        };
-        instruction.load_parameter_positions(memory, position, relative_base);
-        instruction
-    }
-
-    fn load_parameter_positions(&mut self, memory: &Vec<isize>, position: usize, relative_base: usize) {
-        let param_count = self.opcode.parameter_count();
-        let mut parameter_positions: Vec<usize> = Vec::new();
-
-        for param_index in 1..=param_count {
-            parameter_positions.push(match self.parameter_modes[param_index - 1] {
-                    ParameterMode::PositionalMode => match position + param_index < memory.len() {
-                        true => usize::try_from(memory[position + param_index]).unwrap(),
-                        false => 0,
-                    },
-                    ParameterMode::ImmediateMode => position + param_index,
-                    ParameterMode::RelativeMode => match position + param_index < memory.len() {
-                        true => usize::try_from(isize::try_from(relative_base).unwrap() + memory[position + param_index]).unwrap(),
-                        false => usize::try_from(isize::try_from(relative_base).unwrap()).unwrap() + 0,
-                    },
-            });
-        };
 
-        self.parameter_positions = Some(parameter_positions);
+        Ok((opcode, parameter_modes))
     }
 
-    pub fn execute(&self, memory: &mut Vec<isize>, position: &mut usize, relative_base: &mut usize, input: Option<isize>) -> Option<isize> {
-        let parameter_positions = self.parameter_positions.clone().unwrap();
-        let mut output: Option<isize> = None;
-        let mut required_memory_size = memory.len();
-        for parameter_position in parameter_positions.clone() {
-            if parameter_position >= required_memory_size {
-                required_memory_size = parameter_position + 1;
+    // Works out the addresses the parameters of an already decoded instruction refer to
+    pub fn resolve<W: Word, M: Memory<W> + ?Sized>(memory: &M, position: usize, relative_base: usize, opcode: Opcode, parameter_modes: [ParameterMode; MAX_PARAMETERS]) -> Result<Instruction, IntcodeError<W>> {
+        let mut parameter_positions = [0; MAX_PARAMETERS];
+
+        for param_index in 1..=opcode.parameter_count() {
+            let parameter = memory.read(position + param_index);
+            let address = match parameter_modes[param_index - 1] {
+                    ParameterMode::PositionalMode => parameter,
+                    ParameterMode::ImmediateMode => W::from_usize(position + param_index),
+                    ParameterMode::RelativeMode => W::from_usize(relative_base).add_checked(&parameter).ok_or(IntcodeError::ArithmeticOverflow { address: position })?,
             };
-        }
-        memory.resize(required_memory_size, 0);
+            parameter_positions[param_index - 1] = error::address(&address, position)?;
+        };
+
+        Ok(Instruction {
+            opcode,
+            parameter_positions,
+            parameter_modes,
+        })
+    }
+
+    pub fn parameter_modes(&self) -> &[ParameterMode] {
+        &self.parameter_modes[..self.opcode.parameter_count()]
+    }
+
+    pub fn parameter_positions(&self) -> &[usize] {
+        &self.parameter_positions[..self.opcode.parameter_count()]
+    }
+
+    // Positions of the parameters the instruction reads from, paired with their current values
+    pub fn reads<W: Word, M: Memory<W> + ?Sized>(&self, memory: &M) -> Vec<(usize, W)> {
+        self.parameter_positions().iter().enumerate()
+            .filter(|(index, _)| !self.opcode.write_parameters().contains(index))
+            .map(|(_, &position)| (position, memory.read(position)))
+            .collect()
+    }
+
+    pub fn write_positions(&self) -> impl Iterator<Item = usize> + '_ {
+        self.opcode.write_parameters().iter().map(move |&index| self.parameter_positions[index])
+    }
+
+    pub fn execute<W: Word, M: Memory<W> + ?Sized>(&self, memory: &mut M, position: &mut usize, relative_base: &mut usize, input: Option<W>) -> Result<Option<W>, IntcodeError<W>> {
+        self.execute_with(memory, position, relative_base, input, Arithmetic::default())
+    }
+
+    pub fn execute_with<W: Word, M: Memory<W> + ?Sized>(&self, memory: &mut M, position: &mut usize, relative_base: &mut usize, input: Option<W>, arithmetic: Arithmetic) -> Result<Option<W>, IntcodeError<W>> {
+        let parameter_positions = &self.parameter_positions;
+        let mut output: Option<W> = None;
         match self.opcode {
             Opcode::Add => {
-                let result = memory[parameter_positions[0]] + memory[parameter_positions[1]];
+                let result = arithmetic.add(&memory.read(parameter_positions[0]), &memory.read(parameter_positions[1]), *position)?;
 
-                memory[parameter_positions[2]] = result;
+                memory.write(parameter_positions[2], result)?;
                 *position += 4; 
             },
             Opcode::Multiply => {
-                let result = memory[parameter_positions[0]] * memory[parameter_positions[1]];
+                let result = arithmetic.multiply(&memory.read(parameter_positions[0]), &memory.read(parameter_positions[1]), *position)?;
 
-                memory[parameter_positions[2]] = result;
+                memory.write(parameter_positions[2], result)?;
                 *position += 4; 
             },
             Opcode::Input => {
-                memory[parameter_positions[0]] = input.unwrap();
+                memory.write(parameter_positions[0], input.ok_or(IntcodeError::MissingInput { address: *position })?)?;
                 *position += 2;
             },
             Opcode::Output => {
-                output = Some(memory[parameter_positions[0]]);
+                output = Some(memory.read(parameter_positions[0]));
                 *position += 2;
             },
             Opcode::JumpTrue => {
-                *position = match memory[parameter_positions[0]] != 0 {
-                    true => usize::try_from(memory[parameter_positions[1]]).unwrap(),
+                *position = match !memory.read(parameter_positions[0]).is_zero() {
+                    true => error::address(&memory.read(parameter_positions[1]), *position)?,
                     false => *position + 3, 
                 };
             },
             Opcode::JumpFalse => {
-                *position = match memory[parameter_positions[0]] == 0 {
-                    true => usize::try_from(memory[parameter_positions[1]]).unwrap(),
+                *position = match memory.read(parameter_positions[0]).is_zero() {
+                    true => error::address(&memory.read(parameter_positions[1]), *position)?,
                     false => *position + 3, 
                 };
             },
             Opcode::Less => {
-                memory[parameter_positions[2]] = match memory[parameter_positions[0]] < memory[parameter_positions[1]] {
-                    true => 1,
-                    false => 0,
+                let result = match memory.read(parameter_positions[0]) < memory.read(parameter_positions[1]) {
+                    true => W::from_i64(1),
+                    false => W::zero(),
                 };
+                memory.write(parameter_positions[2], result)?;
                 *position += 4;
             },
             Opcode::Equal => {
-                memory[parameter_positions[2]] = match memory[parameter_positions[0]] == memory[parameter_positions[1]] {
-                    true => 1,
-                    false => 0,
+                let result = match memory.read(parameter_positions[0]) == memory.read(parameter_positions[1]) {
+                    true => W::from_i64(1),
+                    false => W::zero(),
                 };
+                memory.write(parameter_positions[2], result)?;
                 *position += 4;
             },
             Opcode::RelativeBase => {
-                *relative_base = usize::try_from(isize::try_from(*relative_base).unwrap() + memory[parameter_positions[0]]).unwrap();
+                let base = W::from_usize(*relative_base).add_checked(&memory.read(parameter_positions[0])).ok_or(IntcodeError::ArithmeticOverflow { address: *position })?;
+                *relative_base = error::address(&base, *position)?;
                 *position += 2
             },
-            _ => panic!("Execute not implemented for opcode: {:?}", self.opcode),
+            Opcode::Halt => (),
+            // Custom instructions can only be run by the registry that defines them
+            Opcode::Custom(custom) => return Err(IntcodeError::UnknownOpcode { opcode: W::from_usize(custom.code), address: *position }),
         };
-        output
+        Ok(output)
     }
 }
 
 #[cfg(test)]
+// Memory in some tests is laid out with zero padded codes
+#[allow(clippy::zero_prefixed_literal)]
 mod tests {
     use super::*;
 
@@ -132,13 +176,13 @@ mod tests {
         let expected_opcode: Opcode = Opcode::Add;
         let expected_parameters: Vec<usize> = vec![1,1,3];
 
-        let memory  = vec![101,1,1,3,99];
-        let instruction = Instruction::from(&memory, 0, 0);
+        let memory: Vec<i64> = vec![101,1,1,3,99];
+        let instruction = Instruction::from(&memory, 0, 0).unwrap();
 
         assert_eq!(instruction.opcode, expected_opcode);
-        assert_eq!(instruction.parameter_positions.unwrap(), expected_parameters);
-        assert_eq!(instruction.parameter_modes, 
-                vec![
+        assert_eq!(instruction.parameter_positions(), expected_parameters.as_slice());
+        assert_eq!(instruction.parameter_modes(), 
+                [
                     ParameterMode::ImmediateMode,
                     ParameterMode::PositionalMode, 
                     ParameterMode::PositionalMode, 
@@ -151,12 +195,12 @@ mod tests {
         let expected_opcode: Opcode = Opcode::Halt;
         let expected_parameters: Vec<usize> = vec![];
 
-        let memory  = vec![99,0,0,3,99];
-        let instruction = Instruction::from(&memory, 0, 0);
+        let memory: Vec<i64> = vec![99,0,0,3,99];
+        let instruction = Instruction::from(&memory, 0, 0).unwrap();
 
         assert_eq!(instruction.opcode, expected_opcode);
-        assert_eq!(instruction.parameter_positions.unwrap(), expected_parameters);
-        assert_eq!(instruction.parameter_modes, vec![]);
+        assert_eq!(instruction.parameter_positions(), expected_parameters.as_slice());
+        assert!(instruction.parameter_modes().is_empty());
     }
 
     #[test]
@@ -164,11 +208,11 @@ mod tests {
         let expected_memory = vec![1,0,0,2,99];
         let expected_position = 4;
 
-        let mut memory = vec![1,0,0,3,99];
-        let instruction = Instruction::from(&memory, 0, 0);
+        let mut memory: Vec<i64> = vec![1,0,0,3,99];
+        let instruction = Instruction::from(&memory, 0, 0).unwrap();
         let mut position = 0;
         let mut relative_base = 0;
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
 
         assert_eq!(memory, expected_memory);
         assert_eq!(position, expected_position);
@@ -179,11 +223,11 @@ mod tests {
         let expected_memory = vec![2,0,3,6,99];
         let expected_position = 4;
 
-        let mut memory = vec![2,0,3,3,99];
-        let instruction = Instruction::from(&memory, 0, 0);
+        let mut memory: Vec<i64> = vec![2,0,3,3,99];
+        let instruction = Instruction::from(&memory, 0, 0).unwrap();
         let mut position = 0;
         let mut relative_base = 0;
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
 
         assert_eq!(memory, expected_memory);
         assert_eq!(position, expected_position);
@@ -194,12 +238,12 @@ mod tests {
         let expected_memory = vec![3,5,99];
         let expected_position = 2;
 
-        let mut memory = vec![3,1,99];
-        let instruction = Instruction::from(&memory, 0, 0);
+        let mut memory: Vec<i64> = vec![3,1,99];
+        let instruction = Instruction::from(&memory, 0, 0).unwrap();
         let mut position = 0;
         let mut relative_base = 0;
-        let input: isize = 5;
-        instruction.execute(&mut memory, &mut position, &mut relative_base, Some(input));
+        let input: i64 = 5;
+        instruction.execute(&mut memory, &mut position, &mut relative_base, Some(input)).unwrap();
 
         assert_eq!(memory, expected_memory);
         assert_eq!(position, expected_position);
@@ -211,11 +255,11 @@ mod tests {
         let expected_position = 2;
         let expected_output = Some(99);
 
-        let mut memory = vec![4,2,99];
-        let instruction = Instruction::from(&memory, 0, 0);
+        let mut memory: Vec<i64> = vec![4,2,99];
+        let instruction = Instruction::from(&memory, 0, 0).unwrap();
         let mut position = 0;
         let mut relative_base = 0;
-        let output = instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        let output = instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
 
         assert_eq!(memory, expected_memory);
         assert_eq!(position, expected_position);
@@ -227,16 +271,16 @@ mod tests {
         let expected_first_position = 3;
         let expected_second_position = 0;
 
-        let mut memory = vec![105,0,0,105,99,2];
+        let mut memory: Vec<i64> = vec![105,0,0,105,99,2];
         let mut position = 0;
         let mut relative_base = 0;
 
-        let instruction = Instruction::from(&memory, position, 0);
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        let instruction = Instruction::from(&memory, position, 0).unwrap();
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
         assert_eq!(position, expected_first_position);
 
-        let instruction = Instruction::from(&memory, position, 0);
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        let instruction = Instruction::from(&memory, position, 0).unwrap();
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
         assert_eq!(position, expected_second_position);
     }
 
@@ -245,16 +289,16 @@ mod tests {
         let expected_first_position = 3;
         let expected_second_position = 5;
 
-        let mut memory = vec![1106,5,99,106,0,1];
+        let mut memory: Vec<i64> = vec![1106,5,99,106,0,1];
         let mut position = 0;
         let mut relative_base = 0;
 
-        let instruction = Instruction::from(&memory, position, 0);
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        let instruction = Instruction::from(&memory, position, 0).unwrap();
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
         assert_eq!(position, expected_first_position);
 
-        let instruction = Instruction::from(&memory, position, 0);
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        let instruction = Instruction::from(&memory, position, 0).unwrap();
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
         assert_eq!(position, expected_second_position);
     }
 
@@ -263,14 +307,14 @@ mod tests {
         let expected_memory = vec![0,0,1,0,1007,0,1,2,99];
         let expected_position = 8;
 
-        let mut memory = vec![1007,0,99,0,1007,0,1,2,99];
+        let mut memory: Vec<i64> = vec![1007,0,99,0,1007,0,1,2,99];
         let mut position = 0;
         let mut relative_base = 0;
-        let instruction = Instruction::from(&memory, position, 0);
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        let instruction = Instruction::from(&memory, position, 0).unwrap();
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
 
-        let instruction = Instruction::from(&memory, position, 0);
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        let instruction = Instruction::from(&memory, position, 0).unwrap();
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
 
         assert_eq!(memory, expected_memory);
         assert_eq!(position, expected_position);
@@ -281,14 +325,14 @@ mod tests {
         let expected_memory = vec![1,0,99,0,08,0,1,1,99];
         let expected_position = 8;
 
-        let mut memory = vec![1108,99,99,0,08,0,1,1,99];
+        let mut memory: Vec<i64> = vec![1108,99,99,0,08,0,1,1,99];
         let mut position = 0;
         let mut relative_base = 0;
-        let instruction = Instruction::from(&memory, position, 0);
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        let instruction = Instruction::from(&memory, position, 0).unwrap();
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
 
-        let instruction = Instruction::from(&memory, position, 0);
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        let instruction = Instruction::from(&memory, position, 0).unwrap();
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
 
         assert_eq!(memory, expected_memory);
         assert_eq!(position, expected_position);
@@ -299,13 +343,13 @@ mod tests {
         let expected_memory = vec![109,2,109,5,99];
         let expected_relative_base = 7;
 
-        let mut memory = vec![109,2,109,5,99];
+        let mut memory: Vec<i64> = vec![109,2,109,5,99];
         let mut position = 0;
         let mut relative_base = 0;
-        let instruction = Instruction::from(&memory, position, 0);
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
-        let instruction = Instruction::from(&memory, position, relative_base);
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        let instruction = Instruction::from(&memory, position, 0).unwrap();
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
+        let instruction = Instruction::from(&memory, position, relative_base).unwrap();
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
 
         assert_eq!(memory, expected_memory);
         assert_eq!(relative_base, expected_relative_base);
@@ -315,22 +359,132 @@ mod tests {
     fn test_accessing_memory_out_of_range() {
         let expected_memory = vec![1,5,1,7,99,0,0,5];
 
-        let mut memory = vec![1,5,1,7,99];
-        let instruction = Instruction::from(&memory, 0, 0);
+        let mut memory: Vec<i64> = vec![1,5,1,7,99];
+        let instruction = Instruction::from(&memory, 0, 0).unwrap();
         let mut position = 0;
         let mut relative_base = 0;
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
 
         assert_eq!(memory, expected_memory);
     }
 
     #[test]
-    #[should_panic]
     fn test_execute_halt() {
-        let mut memory = vec![2,0,3,3,99];
-        let instruction = Instruction::from(&memory, 4, 0);
+        let expected_memory = vec![2,0,3,3,99];
+
+        let mut memory: Vec<i64> = vec![2,0,3,3,99];
+        let instruction = Instruction::from(&memory, 4, 0).unwrap();
         let mut position = 4;
         let mut relative_base = 0;
-        instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+        let output = instruction.execute(&mut memory, &mut position, &mut relative_base, None).unwrap();
+
+        assert_eq!(output, None);
+        assert_eq!(memory, expected_memory);
+        assert_eq!(position, 4);
+    }
+
+    #[test]
+    fn test_from_unknown_opcode() {
+        let memory: Vec<i64> = vec![1,0,0,0,42];
+
+        assert_eq!(Instruction::from(&memory, 4, 0), Err(IntcodeError::UnknownOpcode { opcode: 42, address: 4 }));
+    }
+
+    #[test]
+    fn test_from_negative_opcode() {
+        let memory: Vec<i64> = vec![-1];
+
+        assert_eq!(Instruction::from(&memory, 0, 0), Err(IntcodeError::UnknownOpcode { opcode: -1, address: 0 }));
+    }
+
+    #[test]
+    fn test_from_position_beyond_memory() {
+        let memory: Vec<i64> = vec![1,0,0,0];
+
+        assert_eq!(Instruction::from(&memory, 4, 0), Err(IntcodeError::UnknownOpcode { opcode: 0, address: 4 }));
+    }
+
+    #[test]
+    fn test_from_invalid_parameter_mode() {
+        let memory: Vec<i64> = vec![301,0,0,0];
+
+        assert_eq!(Instruction::from(&memory, 0, 0), Err(IntcodeError::InvalidParameterMode { mode: 3, address: 0 }));
+    }
+
+    #[test]
+    fn test_from_negative_address() {
+        let memory: Vec<i64> = vec![1,-3,0,0];
+
+        assert_eq!(Instruction::from(&memory, 0, 0), Err(IntcodeError::NegativeAddress { address: -3, position: 0 }));
+    }
+
+    #[test]
+    fn test_from_negative_relative_address() {
+        let memory: Vec<i64> = vec![204,-6,99];
+
+        assert_eq!(Instruction::from(&memory, 0, 5), Err(IntcodeError::NegativeAddress { address: -1, position: 0 }));
+    }
+
+    #[test]
+    fn test_from_write_to_immediate() {
+        let memory: Vec<i64> = vec![10001,0,0,0,99];
+
+        assert_eq!(Instruction::from(&memory, 0, 0), Err(IntcodeError::WriteToImmediate { address: 0 }));
+    }
+
+    #[test]
+    fn test_execute_input_missing() {
+        let mut memory: Vec<i64> = vec![3,1,99];
+        let instruction = Instruction::from(&memory, 0, 0).unwrap();
+        let mut position = 0;
+        let mut relative_base = 0;
+
+        let result = instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+
+        assert_eq!(result, Err(IntcodeError::MissingInput { address: 0 }));
+    }
+
+    #[test]
+    fn test_execute_jump_to_negative_address() {
+        let mut memory: Vec<i64> = vec![1105,1,-4];
+        let instruction = Instruction::from(&memory, 0, 0).unwrap();
+        let mut position = 0;
+        let mut relative_base = 0;
+
+        let result = instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+
+        assert_eq!(result, Err(IntcodeError::NegativeAddress { address: -4, position: 0 }));
+    }
+
+    #[test]
+    fn test_execute_overflow() {
+        let mut memory: Vec<i64> = vec![1102,i64::MAX,2,5,99,0];
+        let instruction = Instruction::from(&memory, 0, 0).unwrap();
+        let mut position = 0;
+        let mut relative_base = 0;
+
+        let result = instruction.execute(&mut memory, &mut position, &mut relative_base, None);
+
+        assert_eq!(result, Err(IntcodeError::ArithmeticOverflow { address: 0 }));
+        assert_eq!(position, 0);
+    }
+
+    #[test]
+    fn test_execute_wrapping() {
+        let mut memory: Vec<i64> = vec![1101,i64::MAX,1,5,99,0];
+        let instruction = Instruction::from(&memory, 0, 0).unwrap();
+        let mut position = 0;
+        let mut relative_base = 0;
+
+        instruction.execute_with(&mut memory, &mut position, &mut relative_base, None, Arithmetic::Wrapping).unwrap();
+
+        assert_eq!(memory[5], i64::MIN);
+    }
+
+    #[test]
+    fn test_from_relative_address_overflow() {
+        let memory: Vec<i64> = vec![204,i64::MAX,99];
+
+        assert_eq!(Instruction::from(&memory, 0, 1), Err(IntcodeError::ArithmeticOverflow { address: 0 }));
     }
 }
@@ -1,5 +1,6 @@
 #[derive(Debug)]
 #[derive(PartialEq)]
+#[derive(Clone, Copy)]
 pub enum Opcode {
     Add,
     Multiply,
@@ -11,22 +12,51 @@ pub enum Opcode {
     Equal,
     RelativeBase,
     Halt,
+    // An instruction added through an OpcodeRegistry
+    Custom(CustomOpcode),
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone, Copy)]
+pub struct CustomOpcode {
+    pub code: usize,
+    pub mnemonic: &'static str,
+    pub parameter_count: usize,
+    // Indices of the parameters it writes to, none of which may be in immediate mode
+    pub write_parameters: &'static [usize],
 }
 
 impl Opcode {
-    pub fn from(opcode: usize) -> Opcode {
+    pub fn from(opcode: usize) -> Option<Opcode> {
         match opcode {
-            1 => Opcode::Add,
-            2 => Opcode::Multiply,
-            3 => Opcode::Input,
-            4 => Opcode::Output,
-            5 => Opcode::JumpTrue,
-            6 => Opcode::JumpFalse,
-            7 => Opcode::Less,
-            8 => Opcode::Equal,
-            9 => Opcode::RelativeBase,
-            99 => Opcode::Halt,
-            _ => panic!("Opcode not recognised: {}", opcode),
+            1 => Some(Opcode::Add),
+            2 => Some(Opcode::Multiply),
+            3 => Some(Opcode::Input),
+            4 => Some(Opcode::Output),
+            5 => Some(Opcode::JumpTrue),
+            6 => Some(Opcode::JumpFalse),
+            7 => Some(Opcode::Less),
+            8 => Some(Opcode::Equal),
+            9 => Some(Opcode::RelativeBase),
+            99 => Some(Opcode::Halt),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> usize {
+        match self {
+            Opcode::Add => 1,
+            Opcode::Multiply => 2,
+            Opcode::Input => 3,
+            Opcode::Output => 4,
+            Opcode::JumpTrue => 5,
+            Opcode::JumpFalse => 6,
+            Opcode::Less => 7,
+            Opcode::Equal => 8,
+            Opcode::RelativeBase => 9,
+            Opcode::Halt => 99,
+            Opcode::Custom(custom) => custom.code,
         }
     }
 
@@ -34,15 +64,48 @@ impl Opcode {
         match self {
             Opcode::Add | Opcode::Multiply | Opcode::Less | Opcode::Equal => 3,
             Opcode::JumpTrue | Opcode::JumpFalse => 2,
-            Opcode::Input | Opcode::Output | Opcode:: RelativeBase => 1,
+            Opcode::Input | Opcode::Output | Opcode::RelativeBase => 1,
             Opcode::Halt => 0,
+            Opcode::Custom(custom) => custom.parameter_count,
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Opcode::Add => "ADD",
+            Opcode::Multiply => "MULTIPLY",
+            Opcode::Input => "INPUT",
+            Opcode::Output => "OUTPUT",
+            Opcode::JumpTrue => "JUMPTRUE",
+            Opcode::JumpFalse => "JUMPFALSE",
+            Opcode::Less => "LESS",
+            Opcode::Equal => "EQUAL",
+            Opcode::RelativeBase => "RELATIVEBASE",
+            Opcode::Halt => "HALT",
+            Opcode::Custom(custom) => custom.mnemonic,
+        }
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Opcode> {
+        [1, 2, 3, 4, 5, 6, 7, 8, 9, 99].iter()
+            .filter_map(|&code| Opcode::from(code))
+            .find(|opcode| opcode.mnemonic().eq_ignore_ascii_case(mnemonic))
+    }
+
+    // Indices of the parameters the instruction writes its results to
+    pub fn write_parameters(&self) -> &'static [usize] {
+        match self {
+            Opcode::Add | Opcode::Multiply | Opcode::Less | Opcode::Equal => &[2],
+            Opcode::Input => &[0],
+            Opcode::Custom(custom) => custom.write_parameters,
+            _ => &[],
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Opcode;
+    use super::{CustomOpcode, Opcode};
 
     #[test]
     fn test_opcode_from() {
@@ -57,16 +120,23 @@ mod tests {
         let opcode_from_9 = Opcode::from(9);
         let opcode_from_99 = Opcode::from(99);
 
-        assert_eq!(opcode_from_1, Opcode::Add);
-        assert_eq!(opcode_from_2, Opcode::Multiply);
-        assert_eq!(opcode_from_3, Opcode::Input);
-        assert_eq!(opcode_from_4, Opcode::Output);
-        assert_eq!(opcode_from_5, Opcode::JumpTrue);
-        assert_eq!(opcode_from_6, Opcode::JumpFalse);
-        assert_eq!(opcode_from_7, Opcode::Less);
-        assert_eq!(opcode_from_8, Opcode::Equal);
-        assert_eq!(opcode_from_9, Opcode::RelativeBase);
-        assert_eq!(opcode_from_99, Opcode::Halt);
+        assert_eq!(opcode_from_1, Some(Opcode::Add));
+        assert_eq!(opcode_from_2, Some(Opcode::Multiply));
+        assert_eq!(opcode_from_3, Some(Opcode::Input));
+        assert_eq!(opcode_from_4, Some(Opcode::Output));
+        assert_eq!(opcode_from_5, Some(Opcode::JumpTrue));
+        assert_eq!(opcode_from_6, Some(Opcode::JumpFalse));
+        assert_eq!(opcode_from_7, Some(Opcode::Less));
+        assert_eq!(opcode_from_8, Some(Opcode::Equal));
+        assert_eq!(opcode_from_9, Some(Opcode::RelativeBase));
+        assert_eq!(opcode_from_99, Some(Opcode::Halt));
+    }
+
+    #[test]
+    fn test_opcode_from_unknown() {
+        assert_eq!(Opcode::from(0), None);
+        assert_eq!(Opcode::from(10), None);
+        assert_eq!(Opcode::from(98), None);
     }
 
     #[test]
@@ -82,4 +152,52 @@ mod tests {
         assert_eq!(Opcode::RelativeBase.parameter_count(), 1);
         assert_eq!(Opcode::Halt.parameter_count(), 0);
     }
+
+    #[test]
+    fn test_write_parameters() {
+        assert_eq!(Opcode::Add.write_parameters(), &[2]);
+        assert_eq!(Opcode::Multiply.write_parameters(), &[2]);
+        assert_eq!(Opcode::Input.write_parameters(), &[0]);
+        assert_eq!(Opcode::Output.write_parameters(), &[]);
+        assert_eq!(Opcode::JumpTrue.write_parameters(), &[]);
+        assert_eq!(Opcode::JumpFalse.write_parameters(), &[]);
+        assert_eq!(Opcode::Less.write_parameters(), &[2]);
+        assert_eq!(Opcode::Equal.write_parameters(), &[2]);
+        assert_eq!(Opcode::RelativeBase.write_parameters(), &[]);
+        assert_eq!(Opcode::Halt.write_parameters(), &[]);
+    }
+
+    #[test]
+    fn test_mnemonic() {
+        assert_eq!(Opcode::Add.mnemonic(), "ADD");
+        assert_eq!(Opcode::JumpFalse.mnemonic(), "JUMPFALSE");
+        assert_eq!(Opcode::RelativeBase.mnemonic(), "RELATIVEBASE");
+        assert_eq!(Opcode::Halt.mnemonic(), "HALT");
+    }
+
+    #[test]
+    fn test_custom() {
+        let opcode = Opcode::Custom(CustomOpcode { code: 42, mnemonic: "SWAP", parameter_count: 2, write_parameters: &[0, 1] });
+
+        assert_eq!(opcode.code(), 42);
+        assert_eq!(opcode.mnemonic(), "SWAP");
+        assert_eq!(opcode.parameter_count(), 2);
+        assert_eq!(opcode.write_parameters(), &[0, 1]);
+        assert_eq!(Opcode::from(42), None);
+    }
+
+    #[test]
+    fn test_code_round_trip() {
+        for code in [1, 2, 3, 4, 5, 6, 7, 8, 9, 99].iter() {
+            assert_eq!(Opcode::from(*code).unwrap().code(), *code);
+        }
+    }
+
+    #[test]
+    fn test_from_mnemonic() {
+        assert_eq!(Opcode::from_mnemonic("ADD"), Some(Opcode::Add));
+        assert_eq!(Opcode::from_mnemonic("jumptrue"), Some(Opcode::JumpTrue));
+        assert_eq!(Opcode::from_mnemonic("Halt"), Some(Opcode::Halt));
+        assert_eq!(Opcode::from_mnemonic("NOP"), None);
+    }
 }
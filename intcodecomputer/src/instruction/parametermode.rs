@@ -1,5 +1,7 @@
 #[derive(Debug)]
 #[derive(PartialEq)]
+#[derive(Clone, Copy)]
+#[allow(clippy::enum_variant_names)]
 pub enum ParameterMode {
     PositionalMode,
     ImmediateMode,
@@ -7,12 +9,20 @@ pub enum ParameterMode {
 }
 
 impl ParameterMode {
-    pub fn from(input: usize) -> ParameterMode {
+    pub fn from(input: usize) -> Option<ParameterMode> {
         match input {
-            0 => ParameterMode::PositionalMode,
-            1 => ParameterMode::ImmediateMode,
-            2 => ParameterMode::RelativeMode,
-            _ => panic!("ParameterMode not implemented: {}", input),
+            0 => Some(ParameterMode::PositionalMode),
+            1 => Some(ParameterMode::ImmediateMode),
+            2 => Some(ParameterMode::RelativeMode),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> usize {
+        match self {
+            ParameterMode::PositionalMode => 0,
+            ParameterMode::ImmediateMode => 1,
+            ParameterMode::RelativeMode => 2,
         }
     }
 }
@@ -23,8 +33,20 @@ mod tests {
 
     #[test]
     fn test_from() {
-        assert_eq!(ParameterMode::from(0), ParameterMode::PositionalMode);
-        assert_eq!(ParameterMode::from(1), ParameterMode::ImmediateMode);
-        assert_eq!(ParameterMode::from(2), ParameterMode::RelativeMode);
+        assert_eq!(ParameterMode::from(0), Some(ParameterMode::PositionalMode));
+        assert_eq!(ParameterMode::from(1), Some(ParameterMode::ImmediateMode));
+        assert_eq!(ParameterMode::from(2), Some(ParameterMode::RelativeMode));
+    }
+
+    #[test]
+    fn test_code() {
+        for code in 0..3 {
+            assert_eq!(ParameterMode::from(code).unwrap().code(), code);
+        }
+    }
+
+    #[test]
+    fn test_from_unknown() {
+        assert_eq!(ParameterMode::from(3), None);
     }
 }
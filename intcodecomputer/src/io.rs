@@ -0,0 +1,179 @@
+use crate::word::Word;
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, StdinLock, Stdout, Write};
+use std::sync::mpsc::{Receiver, Sender};
+
+pub trait InputSource<W: Word = i64> {
+    // None when no input is available, which leaves the computer awaiting input
+    fn next_input(&mut self) -> Option<W>;
+}
+
+pub trait OutputSink<W: Word = i64> {
+    fn send_output(&mut self, output: W);
+}
+
+impl<W: Word> InputSource<W> for Vec<W> {
+    fn next_input(&mut self) -> Option<W> {
+        match self.is_empty() {
+            true => None,
+            false => Some(self.remove(0)),
+        }
+    }
+}
+
+impl<W: Word> InputSource<W> for VecDeque<W> {
+    fn next_input(&mut self) -> Option<W> {
+        self.pop_front()
+    }
+}
+
+impl<W: Word, F: FnMut() -> Option<W>> InputSource<W> for F {
+    fn next_input(&mut self) -> Option<W> {
+        self()
+    }
+}
+
+// Blocks until a value arrives, and runs out once every sender has been dropped
+impl<W: Word> InputSource<W> for Receiver<W> {
+    fn next_input(&mut self) -> Option<W> {
+        self.recv().ok()
+    }
+}
+
+impl<W: Word> OutputSink<W> for Vec<W> {
+    fn send_output(&mut self, output: W) {
+        self.push(output);
+    }
+}
+
+impl<W: Word> OutputSink<W> for VecDeque<W> {
+    fn send_output(&mut self, output: W) {
+        self.push_back(output);
+    }
+}
+
+impl<W: Word, F: FnMut(W)> OutputSink<W> for F {
+    fn send_output(&mut self, output: W) {
+        self(output)
+    }
+}
+
+// Outputs sent after the receiver has gone are dropped
+impl<W: Word> OutputSink<W> for Sender<W> {
+    fn send_output(&mut self, output: W) {
+        let _ = self.send(output);
+    }
+}
+
+// Reads one integer per line, skipping blank lines and stopping at the first line that is not a number
+pub struct LineInput<R: BufRead> {
+    reader: R,
+}
+
+impl<R: BufRead> LineInput<R> {
+    pub fn new(reader: R) -> LineInput<R> {
+        LineInput { reader }
+    }
+}
+
+impl LineInput<StdinLock<'static>> {
+    pub fn stdin() -> LineInput<StdinLock<'static>> {
+        LineInput::new(io::stdin().lock())
+    }
+}
+
+impl<R: BufRead, W: Word> InputSource<W> for LineInput<R> {
+    fn next_input(&mut self) -> Option<W> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            if !line.trim().is_empty() {
+                return W::parse_word(&line).ok();
+            }
+        }
+    }
+}
+
+// Writes each output on its own line
+pub struct LineOutput<T: Write> {
+    writer: T,
+}
+
+impl<T: Write> LineOutput<T> {
+    pub fn new(writer: T) -> LineOutput<T> {
+        LineOutput { writer }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.writer
+    }
+}
+
+impl LineOutput<Stdout> {
+    pub fn stdout() -> LineOutput<Stdout> {
+        LineOutput::new(io::stdout())
+    }
+}
+
+impl<T: Write, W: Word> OutputSink<W> for LineOutput<T> {
+    fn send_output(&mut self, output: W) {
+        let _ = writeln!(self.writer, "{}", output).and_then(|_| self.writer.flush());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BigInt;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_vec_input_is_consumed_in_order() {
+        let mut input: Vec<i64> = vec![1,2];
+
+        assert_eq!(input.next_input(), Some(1));
+        assert_eq!(input.next_input(), Some(2));
+        assert_eq!(input.next_input(), None);
+    }
+
+    #[test]
+    fn test_line_input() {
+        let mut input = LineInput::new("5\n\n-3\nend\n7\n".as_bytes());
+
+        assert_eq!(input.next_input(), Some(5i64));
+        assert_eq!(input.next_input(), Some(-3i64));
+        assert_eq!(input.next_input(), None::<i64>);
+    }
+
+    #[test]
+    fn test_line_input_big_words() {
+        let mut input = LineInput::new("-123456789012345678901234567890\n".as_bytes());
+
+        assert_eq!(input.next_input(), Some("-123456789012345678901234567890".parse::<BigInt>().unwrap()));
+    }
+
+    #[test]
+    fn test_line_output() {
+        let mut output = LineOutput::new(Vec::new());
+
+        output.send_output(12i64);
+        output.send_output(BigInt::from(-4i64));
+
+        assert_eq!(output.into_inner(), b"12\n-4\n");
+    }
+
+    #[test]
+    fn test_channels() {
+        let (mut sender, mut receiver) = channel();
+
+        sender.send_output(3i64);
+        drop(sender);
+
+        assert_eq!(receiver.next_input(), Some(3));
+        assert_eq!(receiver.next_input(), None);
+    }
+}
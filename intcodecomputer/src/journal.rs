@@ -0,0 +1,123 @@
+use crate::word::Word;
+
+use std::collections::VecDeque;
+
+// Enough about one executed instruction to undo it
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub struct JournalEntry<W: Word = i64> {
+    pub step: usize,
+    pub position: usize,
+    pub relative_base: usize,
+    // (address, old value, new value) in the order they were written
+    pub writes: Vec<(usize, W, W)>,
+    pub input: Option<W>,
+    pub output: Option<W>,
+}
+
+// Keeps the most recent entries up to its capacity, dropping the oldest first
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Journal<W: Word = i64> {
+    entries: VecDeque<JournalEntry<W>>,
+    capacity: usize,
+    steps: usize,
+}
+
+impl<W: Word> Journal<W> {
+    pub fn new(capacity: usize) -> Journal<W> {
+        Journal {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            steps: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // Instructions executed since the journal was started
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    // The earliest step that can still be rewound to
+    pub fn oldest_step(&self) -> usize {
+        self.entries.front().map_or(self.steps, |entry| entry.step)
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &JournalEntry<W>> {
+        self.entries.iter()
+    }
+
+    pub fn last_output(&self) -> Option<&JournalEntry<W>> {
+        self.entries.iter().rev().find(|entry| entry.output.is_some())
+    }
+
+    // The most recent write to an address made before the given step
+    pub fn last_write_before(&self, address: usize, step: usize) -> Option<&JournalEntry<W>> {
+        self.entries.iter().rev()
+            .filter(|entry| entry.step < step)
+            .find(|entry| entry.writes.iter().any(|(written, _, _)| *written == address))
+    }
+
+    pub(crate) fn record(&mut self, mut entry: JournalEntry<W>) {
+        entry.step = self.steps;
+        self.steps += 1;
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<JournalEntry<W>> {
+        let entry = self.entries.pop_back()?;
+        self.steps = entry.step;
+        Some(entry)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.steps = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(position: usize) -> JournalEntry {
+        JournalEntry { step: 0, position, relative_base: 0, writes: vec![], input: None, output: None }
+    }
+
+    #[test]
+    fn test_ring_buffer_drops_oldest() {
+        let mut journal: Journal = Journal::new(2);
+
+        for position in 0..5 {
+            journal.record(entry(position));
+        }
+
+        assert_eq!(journal.steps(), 5);
+        assert_eq!(journal.oldest_step(), 3);
+        assert_eq!(journal.entries().map(|entry| entry.position).collect::<Vec<usize>>(), vec![3,4]);
+    }
+
+    #[test]
+    fn test_pop_moves_steps_back() {
+        let mut journal: Journal = Journal::new(4);
+        journal.record(entry(0));
+        journal.record(entry(4));
+
+        assert_eq!(journal.pop().map(|entry| entry.position), Some(4));
+        assert_eq!(journal.steps(), 1);
+        assert_eq!(journal.pop().map(|entry| entry.step), Some(0));
+        assert_eq!(journal.pop(), None);
+        assert_eq!(journal.steps(), 0);
+    }
+}
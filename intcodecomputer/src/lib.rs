@@ -1,66 +1,605 @@
+pub mod ascii;
+pub mod assembler;
+pub mod controlflow;
+pub mod coverage;
+pub mod debugger;
+pub mod disassembler;
+mod error;
+pub mod fuzz;
 mod instruction;
-use instruction::Instruction;
-use instruction::opcode::Opcode;
+pub mod io;
+pub mod journal;
+mod limits;
+pub mod memory;
+pub mod modification;
+pub mod network;
+pub mod packet;
+pub mod profiler;
+pub mod registry;
+pub mod snapshot;
+mod step;
+pub mod terminal;
+pub mod trace;
+pub mod word;
+pub use ascii::{AsciiComputer, AsciiEvent, AsciiOutput};
+pub use controlflow::ControlFlowGraph;
+pub use coverage::Coverage;
+pub use error::IntcodeError;
+pub use instruction::{Instruction, MAX_PARAMETERS};
+pub use instruction::arithmetic::Arithmetic;
+pub use instruction::opcode::{CustomOpcode, Opcode};
+pub use instruction::parametermode::ParameterMode;
+pub use io::{InputSource, OutputSink};
+pub use journal::{Journal, JournalEntry};
+pub use memory::{Memory, PagedMemory};
+pub use modification::Modifications;
+pub use profiler::Profile;
+pub use registry::{Context, OpcodeRegistry, UnknownOpcodeAction};
+pub use snapshot::{Snapshot, SnapshotError};
+pub use step::{Step, StepOutcome};
+pub use trace::{TraceFormat, TraceReader, TraceRecord, TraceWriter};
+pub use word::{BigInt, ParseWordError, Word};
 
-use std::collections::VecDeque;
+use limits::{LoopDetector, RunGuard};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::time::Duration;
 
-pub struct IntcodeComputer {
-    pub memory: Vec<isize>,
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum RunState<W: Word = i64> {
+    Halted,
+    AwaitingInput,
+    Output(W),
+}
+
+pub fn parse_program<W: Word>(text: &str) -> Result<Vec<W>, ParseWordError> {
+    text.trim().split(',').map(W::parse_word).collect()
+}
+
+// For the binaries: reads and parses a program file, with a message naming the file if either fails
+pub fn load_program(path: &str) -> Result<Vec<i64>, String> {
+    fs::read_to_string(path)
+        .map_err(|error| error.to_string())
+        .and_then(|text| parse_program(&text).map_err(|error| error.to_string()))
+        .map_err(|error| format!("Could not load {}: {}", path, error))
+}
+
+// For the binaries: parses input values given as arguments, with a message naming the first that is not a number
+pub fn parse_inputs(arguments: &[String]) -> Result<Vec<i64>, String> {
+    arguments.iter()
+        .map(|input| input.parse::<i64>().map_err(|_| format!("Not a valid input: {}", input)))
+        .collect()
+}
+
+enum Next<W: Word> {
+    Ready(Instruction, Option<W>),
+    Blocked(RunState<W>),
+}
+
+// A cached decode is only reused while the code at its address is unchanged, so writes invalidate it
+#[derive(Clone)]
+struct CachedDecode<W: Word> {
+    code: W,
+    opcode: Opcode,
+    parameter_modes: [ParameterMode; MAX_PARAMETERS],
+}
+
+// Dense over the program image, where nearly all code runs, and keyed by address beyond it so that a jump far out
+// does not allocate every entry up to it
+struct DecodeCache<W: Word> {
+    image: Vec<Option<CachedDecode<W>>>,
+    far: HashMap<usize, CachedDecode<W>>,
+}
+
+impl<W: Word> DecodeCache<W> {
+    fn new() -> DecodeCache<W> {
+        DecodeCache {
+            image: vec![],
+            far: HashMap::new(),
+        }
+    }
+
+    fn get(&self, address: usize) -> Option<&CachedDecode<W>> {
+        match address < self.image.len() {
+            true => self.image[address].as_ref(),
+            false => self.far.get(&address),
+        }
+    }
+
+    // Entries beyond the image count against the memory limit like any other cell, and are simply not cached once
+    // they would go over it
+    fn insert(&mut self, address: usize, decoded: CachedDecode<W>, memory: &PagedMemory<W>) {
+        let image_size = memory.image().len();
+        let room = match memory.limit() {
+            Some(limit) => memory.allocated() + self.far.len() < limit,
+            None => true,
+        };
+        if address < image_size {
+            if self.image.len() < image_size {
+                self.image.resize(image_size, None);
+            }
+            self.image[address] = Some(decoded);
+        } else if room {
+            self.far.insert(address, decoded);
+        }
+    }
+
+    fn evict(&mut self, address: usize) {
+        match self.image.get_mut(address) {
+            Some(cached) => *cached = None,
+            None => {
+                self.far.remove(&address);
+            },
+        }
+    }
+
+    fn clear(&mut self) {
+        self.image.clear();
+        self.far.clear();
+    }
+}
+
+pub struct IntcodeComputer<W: Word = i64> {
+    pub memory: PagedMemory<W>,
     position: usize,
     relative_base: usize,
+    inputs: VecDeque<W>,
+    decode_cache: Option<DecodeCache<W>>,
+    journal: Option<Journal<W>>,
+    trace: Option<TraceWriter>,
+    profile: Option<Profile>,
+    coverage: Option<Coverage>,
+    modifications: Option<Modifications>,
+    instruction_budget: Option<usize>,
+    time_limit: Option<Duration>,
+    loop_detector: Option<LoopDetector<W>>,
+    arithmetic: Arithmetic,
+    opcodes: OpcodeRegistry<W>,
+    // Where a custom instruction halted the computer
+    halted_at: Option<usize>,
 }
 
+// Other word types are picked with IntcodeComputer::<W>::default()
 impl IntcodeComputer {
     pub fn new() -> IntcodeComputer {
+        IntcodeComputer::default()
+    }
+}
+
+impl<W: Word> Default for IntcodeComputer<W> {
+    fn default() -> Self {
         IntcodeComputer {
-            memory: Vec::new(),
+            memory: PagedMemory::new(),
             position: 0,
             relative_base: 0,
+            inputs: VecDeque::new(),
+            decode_cache: None,
+            journal: None,
+            trace: None,
+            profile: None,
+            coverage: None,
+            modifications: None,
+            instruction_budget: None,
+            time_limit: None,
+            loop_detector: None,
+            arithmetic: Arithmetic::default(),
+            opcodes: OpcodeRegistry::new(),
+            halted_at: None,
         }
     }
+}
 
-    pub fn load(&mut self, program: &Vec<isize>) {
-        self.memory = program.clone();
+impl<W: Word> IntcodeComputer<W> {
+    pub fn load(&mut self, program: &[W]) {
+        self.memory.load(program);
         self.position = 0;
         self.relative_base = 0;
+        self.inputs.clear();
+        self.halted_at = None;
+        if let Some(cache) = &mut self.decode_cache {
+            cache.clear();
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.clear();
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot<W> {
+        Snapshot {
+            memory: self.memory.clone(),
+            position: self.position,
+            relative_base: self.relative_base,
+            inputs: self.inputs.iter().cloned().collect(),
+            halted_at: self.halted_at,
+        }
+    }
+
+    // Keeps this computer's memory limit and decode cache setting
+    pub fn restore(&mut self, snapshot: &Snapshot<W>) {
+        let limit = self.memory.limit();
+        self.memory = snapshot.memory.clone();
+        self.memory.set_limit(limit);
+        self.position = snapshot.position;
+        self.relative_base = snapshot.relative_base;
+        self.inputs = snapshot.inputs.iter().cloned().collect();
+        self.halted_at = snapshot.halted_at;
+        if let Some(cache) = &mut self.decode_cache {
+            cache.clear();
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.clear();
+        }
     }
 
-    pub fn run(&mut self, input_option: Option<Vec<isize>>) -> Vec<isize> {
-        let mut instruction = self.load_instruction();
-        let mut inputs: VecDeque<isize> = match input_option {
-            Some(input_options) => VecDeque::from(input_options),
-            None => VecDeque::new(),
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory.set_limit(limit);
+    }
+
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.decode_cache = match enabled {
+            true => Some(DecodeCache::new()),
+            false => None,
         };
-        let mut outputs: Vec<isize> = vec![];
+    }
 
-        while instruction.opcode != Opcode::Halt {
-            if (instruction.opcode == Opcode::Input ) && inputs.len() == 0 {
-                break;
-            }
-            let input = match instruction.opcode == Opcode::Input {
-                true => Some(inputs.pop_front().unwrap()),
-                false => None,
+    // Records up to capacity executed instructions so they can be stepped back through
+    pub fn set_journal(&mut self, capacity: Option<usize>) {
+        self.journal = capacity.map(Journal::new);
+    }
+
+    pub fn journal(&self) -> Option<&Journal<W>> {
+        self.journal.as_ref()
+    }
+
+    // Undoes the most recent journaled instruction, handing back any input it consumed
+    pub fn step_back(&mut self) -> Option<JournalEntry<W>> {
+        let entry = self.journal.as_mut()?.pop()?;
+        // Undone last to first so a cell written twice ends up with the value from before both. Each cell was
+        // written before, so restoring it never needs a new page.
+        for (address, old_value, _) in entry.writes.iter().rev() {
+            let _ = self.memory.write(*address, old_value.clone());
+        }
+        if let Some(input) = &entry.input {
+            self.inputs.push_front(input.clone());
+        }
+        self.position = entry.position;
+        self.relative_base = entry.relative_base;
+        self.halted_at = None;
+        Some(entry)
+    }
+
+    // Returns false, leaving the computer untouched, when the step is no longer journaled
+    pub fn rewind_to(&mut self, step: usize) -> bool {
+        match &self.journal {
+            Some(journal) if journal.oldest_step() <= step && step <= journal.steps() => (),
+            _ => return false,
+        }
+        while self.journal.as_ref().is_some_and(|journal| journal.steps() > step) {
+            self.step_back();
+        }
+        true
+    }
+
+    // Hands back the trace being replaced so it can be finished
+    pub fn set_trace(&mut self, trace: Option<TraceWriter>) -> Option<TraceWriter> {
+        std::mem::replace(&mut self.trace, trace)
+    }
+
+    // Starts a fresh profile, which keeps counting across loads until profiling is turned off
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profile = match enabled {
+            true => Some(Profile::new()),
+            false => None,
+        };
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    // Like profiling, coverage accumulates across loads until it is turned off
+    pub fn set_coverage(&mut self, enabled: bool) {
+        self.coverage = match enabled {
+            true => Some(Coverage::new()),
+            false => None,
+        };
+    }
+
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    // Reports instructions that write into the instruction stream, accumulating across loads like coverage
+    pub fn set_modification_tracking(&mut self, enabled: bool) {
+        self.modifications = match enabled {
+            true => Some(Modifications::new()),
+            false => None,
+        };
+    }
+
+    pub fn modifications(&self) -> Option<&Modifications> {
+        self.modifications.as_ref()
+    }
+
+    pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+        self.arithmetic = arithmetic;
+    }
+
+    pub fn set_opcodes(&mut self, opcodes: OpcodeRegistry<W>) {
+        self.opcodes = opcodes;
+        if let Some(cache) = &mut self.decode_cache {
+            cache.clear();
+        }
+    }
+
+    pub fn opcodes(&self) -> &OpcodeRegistry<W> {
+        &self.opcodes
+    }
+
+    // The budget and time limit apply afresh to each call that runs the computer, but not to step
+    pub fn set_instruction_budget(&mut self, budget: Option<usize>) {
+        self.instruction_budget = budget;
+    }
+
+    pub fn set_time_limit(&mut self, limit: Option<Duration>) {
+        self.time_limit = limit;
+    }
+
+    // Stops a run that returns to the exact same state without having read an input in between
+    pub fn set_loop_detection(&mut self, enabled: bool) {
+        self.loop_detector = match enabled {
+            true => Some(LoopDetector::new()),
+            false => None,
+        };
+    }
+
+    pub fn push_input(&mut self, input: W) {
+        self.inputs.push_back(input);
+    }
+
+    pub fn run(&mut self, input_option: Option<Vec<W>>) -> Result<Vec<W>, IntcodeError<W>> {
+        if let Some(inputs) = input_option {
+            self.inputs.extend(inputs);
+        }
+        let (_, outputs) = self.resume()?;
+        Ok(outputs)
+    }
+
+    // Runs until the program halts or needs an input that has not been queued yet
+    pub fn resume(&mut self) -> Result<(RunState<W>, Vec<W>), IntcodeError<W>> {
+        let mut outputs: Vec<W> = vec![];
+        let state = self.run_with(&mut || None, &mut outputs)?;
+        Ok((state, outputs))
+    }
+
+    // Queued inputs are used up before the source is asked for more
+    pub fn run_with<I, O>(&mut self, input: &mut I, output: &mut O) -> Result<RunState<W>, IntcodeError<W>>
+    where
+        I: InputSource<W> + ?Sized,
+        O: OutputSink<W> + ?Sized,
+    {
+        let mut guard = self.start_run();
+        loop {
+            let (instruction, input) = match self.next_instruction(input)? {
+                Next::Ready(instruction, input) => (instruction, input),
+                Next::Blocked(state) => return Ok(state),
             };
-            match instruction.execute(&mut self.memory, &mut self.position, &mut self.relative_base, input) {
-                Some(output) => outputs.push(output),
-                None => (),
+            self.check_limits(&mut guard, &input)?;
+            if let Some(value) = self.execute(&instruction, input)? {
+                output.send_output(value);
+            }
+        }
+    }
+
+    pub fn run_until_output(&mut self) -> Result<RunState<W>, IntcodeError<W>> {
+        let mut guard = self.start_run();
+        loop {
+            let (instruction, input) = match self.next_instruction(&mut || None)? {
+                Next::Ready(instruction, input) => (instruction, input),
+                Next::Blocked(state) => return Ok(state),
             };
-            instruction = self.load_instruction();
+            self.check_limits(&mut guard, &input)?;
+            if let Some(output) = self.execute(&instruction, input)? {
+                return Ok(RunState::Output(output));
+            }
+        }
+    }
+
+    pub fn step(&mut self) -> Result<StepOutcome<W>, IntcodeError<W>> {
+        let (instruction, input) = match self.next_instruction(&mut || None)? {
+            Next::Ready(instruction, input) => (instruction, input),
+            Next::Blocked(RunState::AwaitingInput) => return Ok(StepOutcome::AwaitingInput),
+            Next::Blocked(_) => return Ok(StepOutcome::Halted),
+        };
+        Ok(StepOutcome::Executed(self.execute_recorded(&instruction, input)?))
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn relative_base(&self) -> usize {
+        self.relative_base
+    }
+
+    pub fn instruction(&self) -> Result<Instruction, IntcodeError<W>> {
+        let (opcode, parameter_modes) = self.opcodes.decode(&self.memory.read(self.position), self.position)?;
+        Instruction::resolve(&self.memory, self.position, self.relative_base, opcode, parameter_modes)
+    }
+
+    fn load_instruction(&mut self) -> Result<Instruction, IntcodeError<W>> {
+        let cache = match &mut self.decode_cache {
+            Some(cache) => cache,
+            None => return self.instruction(),
+        };
+        let code = self.memory.read(self.position);
+        let (opcode, parameter_modes) = match cache.get(self.position) {
+            Some(cached) if cached.code == code => (cached.opcode, cached.parameter_modes),
+            _ => {
+                let (opcode, parameter_modes) = self.opcodes.decode(&code, self.position)?;
+                cache.insert(self.position, CachedDecode { code, opcode, parameter_modes }, &self.memory);
+                (opcode, parameter_modes)
+            },
+        };
+        Instruction::resolve(&self.memory, self.position, self.relative_base, opcode, parameter_modes)
+    }
+
+    fn start_run(&mut self) -> RunGuard {
+        if let Some(detector) = &mut self.loop_detector {
+            detector.reset(&self.memory);
         }
+        RunGuard::new(self.instruction_budget, self.time_limit)
+    }
+
+    // An input taken for an instruction that is not going to run goes back on the queue
+    fn check_limits(&mut self, guard: &mut RunGuard, input: &Option<W>) -> Result<(), IntcodeError<W>> {
+        let address = self.position;
+        let loop_length = match &mut self.loop_detector {
+            Some(detector) => detector.check(address, self.relative_base, &self.memory),
+            None => None,
+        };
+        let result = match loop_length {
+            Some(length) => Err(IntcodeError::InfiniteLoop { address, length }),
+            None => guard.check(address),
+        };
+        if let (Err(_), Some(input)) = (&result, input) {
+            self.inputs.push_front(input.clone());
+        }
+        result
+    }
+
+    fn recording(&self) -> bool {
+        self.journal.is_some() || self.trace.is_some() || self.profile.is_some() || self.coverage.is_some() || self.modifications.is_some() || self.loop_detector.is_some()
+    }
+
+    fn execute(&mut self, instruction: &Instruction, input: Option<W>) -> Result<Option<W>, IntcodeError<W>> {
+        match self.recording() {
+            true => Ok(self.execute_recorded(instruction, input)?.output),
+            false => self.execute_instruction(instruction, input),
+        }
+    }
 
-        outputs
+    fn execute_instruction(&mut self, instruction: &Instruction, input: Option<W>) -> Result<Option<W>, IntcodeError<W>> {
+        let custom = match instruction.opcode {
+            Opcode::Custom(custom) => custom,
+            _ => return instruction.execute_with(&mut self.memory, &mut self.position, &mut self.relative_base, input, self.arithmetic),
+        };
+        let address = self.position;
+        let mut context = Context::new(&mut self.memory, instruction, address, self.relative_base, self.arithmetic);
+        // An opcode skipped for the unknown opcode handler has nothing to run
+        if let Some(execute) = self.opcodes.executor(custom) {
+            execute(&mut context)?;
+        }
+        self.relative_base = context.relative_base;
+        self.position = match (context.halt, context.jump) {
+            (true, _) => {
+                self.halted_at = Some(address);
+                address
+            },
+            (false, Some(target)) => target,
+            (false, None) => address + custom.parameter_count + 1,
+        };
+        Ok(context.output)
+    }
+
+    // Executes an instruction while noting what it did for whichever recorders are on, and for the caller
+    fn execute_recorded(&mut self, instruction: &Instruction, input: Option<W>) -> Result<Step<W>, IntcodeError<W>> {
+        let (address, relative_base) = (self.position, self.relative_base);
+        let reads = instruction.reads(&self.memory);
+        let old_writes: Vec<(usize, W)> = instruction.write_positions().map(|position| (position, self.memory.read(position))).collect();
+
+        let output = self.execute_instruction(instruction, input.clone())?;
+
+        let writes: Vec<(usize, W, W)> = old_writes.into_iter().map(|(position, old_value)| (position, old_value, self.memory.read(position))).collect();
+        let jump = match instruction.opcode {
+            Opcode::JumpTrue | Opcode::JumpFalse if (instruction.opcode == Opcode::JumpTrue) != reads[0].1.is_zero() => Some(self.position),
+            Opcode::Custom(custom) if self.position != address + custom.parameter_count + 1 && self.halted_at != Some(address) => Some(self.position),
+            _ => None,
+        };
+        let step = Step {
+            address,
+            opcode: instruction.opcode,
+            parameter_modes: instruction.parameter_modes().to_vec(),
+            parameter_positions: instruction.parameter_positions().to_vec(),
+            reads,
+            writes: writes.iter().map(|(position, _, new_value)| (*position, new_value.clone())).collect(),
+            jump,
+            input: input.clone(),
+            output: output.clone(),
+            relative_base: self.relative_base,
+        };
+        if let Some(detector) = &mut self.loop_detector {
+            detector.record(&writes, input.is_some());
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.record(JournalEntry { step: 0, position: address, relative_base, writes, input, output });
+        }
+        if let Some(trace) = &mut self.trace {
+            trace.record(&step);
+        }
+        if let Some(profile) = &mut self.profile {
+            profile.record(&step);
+        }
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record(&step);
+        }
+        if let Some(modifications) = &mut self.modifications {
+            // Code that was overwritten is decoded afresh rather than checked against its cached code
+            if let (true, Some(cache)) = (modifications.record(&step), &mut self.decode_cache) {
+                for (position, _) in &step.writes {
+                    cache.evict(*position);
+                }
+            }
+        }
+        Ok(step)
     }
 
-    fn load_instruction(&self) -> Instruction {
-        Instruction::from(&self.memory, self.position, self.relative_base)
+    fn next_instruction<I: InputSource<W> + ?Sized>(&mut self, source: &mut I) -> Result<Next<W>, IntcodeError<W>> {
+        if self.halted_at == Some(self.position) {
+            return Ok(Next::Blocked(RunState::Halted));
+        }
+        let instruction = match self.load_instruction() {
+            Err(IntcodeError::UnknownOpcode { opcode, address }) => match self.opcodes.unknown(&opcode, address) {
+                UnknownOpcodeAction::Fail => return Err(IntcodeError::UnknownOpcode { opcode, address }),
+                UnknownOpcodeAction::Halt => return Ok(Next::Blocked(RunState::Halted)),
+                UnknownOpcodeAction::Skip => {
+                    let code = opcode.to_usize().map_or(0, |code| code % 100);
+                    Instruction::resolve(&self.memory, address, self.relative_base, registry::skip(code), [ParameterMode::PositionalMode; MAX_PARAMETERS])?
+                },
+            },
+            result => result?,
+        };
+        let input = match instruction.opcode {
+            Opcode::Halt => {
+                if let Some(coverage) = &mut self.coverage {
+                    coverage.record_halt(self.position);
+                }
+                if let Some(modifications) = &mut self.modifications {
+                    modifications.record_halt(self.position);
+                }
+                return Ok(Next::Blocked(RunState::Halted));
+            },
+            Opcode::Input => match self.inputs.pop_front().or_else(|| source.next_input()) {
+                Some(input) => Some(input),
+                None => return Ok(Next::Blocked(RunState::AwaitingInput)),
+            },
+            _ => None,
+        };
+        Ok(Next::Ready(instruction, input))
     }
 
 }
 
 #[cfg(test)]
+// The day 2 search is kept as first written
+#[allow(unused_labels, clippy::assign_op_pattern)]
 mod tests {
     use super::*;
+    use std::cell::Cell;
     use std::fs;
+    use std::sync::mpsc::channel;
+    use std::thread;
 
     #[test]
     fn test_initialisation() {
@@ -96,10 +635,10 @@ mod tests {
     fn test_program_with_input() {
         let mut computer = IntcodeComputer::new();
         let program = vec![3,1,99];
-        let inputs: Option<Vec<isize>> = Some(vec![7]); 
+        let inputs: Option<Vec<i64>> = Some(vec![7]); 
 
         computer.load(&program);
-        computer.run(inputs);
+        computer.run(inputs).unwrap();
 
         let expected_memory = vec![3,7,99];
 
@@ -114,19 +653,269 @@ mod tests {
         let program = vec![3,1,99];
 
         computer.load(&program);
-        computer.run(None);
+        computer.run(None).unwrap();
 
         assert_eq!(computer.memory, expected_memory);
         assert_eq!(computer.position, 0);
     }
 
+    #[test]
+    fn test_resume_after_awaiting_input() {
+        let mut computer = IntcodeComputer::new();
+        let program = vec![3,9,4,9,3,9,4,9,99,0];
+
+        computer.load(&program);
+        computer.push_input(5);
+
+        assert_eq!(computer.resume().unwrap(), (RunState::AwaitingInput, vec![5]));
+
+        computer.push_input(8);
+
+        assert_eq!(computer.resume().unwrap(), (RunState::Halted, vec![8]));
+        assert_eq!(computer.resume().unwrap(), (RunState::Halted, vec![]));
+    }
+
+    #[test]
+    fn test_inputs_kept_across_runs() {
+        let mut computer = IntcodeComputer::new();
+        let program = vec![3,11,4,11,1105,1,0,0,0,0,0,0];
+
+        computer.load(&program);
+        let outputs = computer.run(Some(vec![1,2,3])).unwrap();
+
+        assert_eq!(outputs, vec![1,2,3]);
+        assert_eq!(computer.resume().unwrap(), (RunState::AwaitingInput, vec![]));
+    }
+
+    #[test]
+    fn test_run_until_output() {
+        let mut computer = IntcodeComputer::new();
+        let program = vec![104,1,104,2,99];
+
+        computer.load(&program);
+
+        assert_eq!(computer.run_until_output().unwrap(), RunState::Output(1));
+        assert_eq!(computer.run_until_output().unwrap(), RunState::Output(2));
+        assert_eq!(computer.run_until_output().unwrap(), RunState::Halted);
+    }
+
+    #[test]
+    fn test_step_add() {
+        let mut computer = IntcodeComputer::new();
+        let program = vec![1001,5,7,5,99,3];
+
+        computer.load(&program);
+        let outcome = computer.step().unwrap();
+
+        assert_eq!(outcome, StepOutcome::Executed(Step {
+            address: 0,
+            opcode: Opcode::Add,
+            parameter_modes: vec![ParameterMode::PositionalMode, ParameterMode::ImmediateMode, ParameterMode::PositionalMode],
+            parameter_positions: vec![5,2,5],
+            reads: vec![(5,3),(2,7)],
+            writes: vec![(5,10)],
+            jump: None,
+            input: None,
+            output: None,
+            relative_base: 0,
+        }));
+        assert_eq!(computer.position(), 4);
+        assert_eq!(computer.step().unwrap(), StepOutcome::Halted);
+        assert_eq!(computer.position(), 4);
+    }
+
+    #[test]
+    fn test_step_jumps() {
+        let mut computer = IntcodeComputer::new();
+        let program = vec![1105,0,9,1106,0,7,99,109,-2,99];
+
+        computer.load(&program);
+
+        match computer.step().unwrap() {
+            StepOutcome::Executed(step) => assert_eq!(step.jump, None),
+            outcome => panic!("Unexpected outcome: {:?}", outcome),
+        }
+        match computer.step().unwrap() {
+            StepOutcome::Executed(step) => assert_eq!(step.jump, Some(7)),
+            outcome => panic!("Unexpected outcome: {:?}", outcome),
+        }
+        assert_eq!(computer.position(), 7);
+        assert_eq!(computer.relative_base(), 0);
+    }
+
+    #[test]
+    fn test_step_input_and_output() {
+        let mut computer = IntcodeComputer::new();
+        let program = vec![109,7,203,0,204,0,99];
+
+        computer.load(&program);
+        computer.step().unwrap();
+
+        assert_eq!(computer.relative_base(), 7);
+        assert_eq!(computer.step().unwrap(), StepOutcome::AwaitingInput);
+
+        computer.push_input(42);
+        match computer.step().unwrap() {
+            StepOutcome::Executed(step) => {
+                assert_eq!(step.input, Some(42));
+                assert_eq!(step.writes, vec![(7,42)]);
+            },
+            outcome => panic!("Unexpected outcome: {:?}", outcome),
+        }
+        match computer.step().unwrap() {
+            StepOutcome::Executed(step) => {
+                assert_eq!(step.reads, vec![(7,42)]);
+                assert_eq!(step.output, Some(42));
+            },
+            outcome => panic!("Unexpected outcome: {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn test_instruction_peek() {
+        let mut computer = IntcodeComputer::new();
+        let program = vec![1002,4,3,4,33];
+
+        computer.load(&program);
+        let instruction = computer.instruction().unwrap();
+
+        assert_eq!(instruction.opcode, Opcode::Multiply);
+        assert_eq!(instruction.parameter_positions(), &[4,2,4]);
+        assert_eq!(computer.position(), 0);
+    }
+
+    #[test]
+    fn test_decode_cache_sees_self_modification() {
+        let mut computer = IntcodeComputer::new();
+        // Outputs from address 0 twice, patching it from immediate to positional mode in between
+        let program = vec![104,7,1005,17,16,1101,4,0,0,1101,1,0,17,1105,1,0,99,0];
+
+        computer.set_decode_cache(true);
+        computer.load(&program);
+
+        assert_eq!(computer.run(None), Ok(vec![7,0]));
+    }
+
+    #[test]
+    fn test_decode_cache_far_jump() {
+        let mut computer = IntcodeComputer::new();
+        // Writes a halt a billion cells out and jumps to it
+        let program = vec![1101,99,0,1000000000,1105,1,1000000000];
+
+        computer.set_decode_cache(true);
+        computer.set_memory_limit(Some(2 * memory::PAGE_SIZE));
+        computer.load(&program);
+
+        assert_eq!(computer.run(None), Ok(vec![]));
+        assert_eq!(computer.decode_cache.as_ref().unwrap().far.len(), 1);
+    }
+
+    #[test]
+    fn test_far_address_is_sparse() {
+        let mut computer = IntcodeComputer::new();
+        let program = vec![21101,6,7,1000000000,204,1000000000,99];
+
+        computer.load(&program);
+
+        assert_eq!(computer.run(None), Ok(vec![13]));
+        assert_eq!(computer.memory[1000000000], 13);
+        assert!(computer.memory.allocated() < 10000);
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let mut computer = IntcodeComputer::new();
+        let program = vec![1101,6,7,1000000000,99];
+
+        computer.set_memory_limit(Some(100));
+        computer.load(&program);
+
+        assert_eq!(computer.run(None), Err(IntcodeError::MemoryLimitExceeded { address: 1000000000, limit: 100 }));
+    }
+
+    #[test]
+    fn test_run_reports_unknown_opcode() {
+        let mut computer = IntcodeComputer::new();
+        let program = vec![1,0,0,0,55];
+
+        computer.load(&program);
+
+        assert_eq!(computer.run(None), Err(IntcodeError::UnknownOpcode { opcode: 55, address: 4 }));
+    }
+
+    #[test]
+    fn test_run_with_input_from_previous_output() {
+        let mut computer = IntcodeComputer::new();
+        // Doubles every input it is given until it reads a zero
+        let program = vec![3,15,1006,15,14,1002,15,2,15,4,15,1105,1,0,99,0];
+        let last_output = Cell::new(1);
+        let mut outputs = vec![];
+
+        computer.load(&program);
+        let state = computer.run_with(&mut || match last_output.get() {
+            output if output < 100 => Some(output),
+            _ => Some(0),
+        }, &mut |output| {
+            last_output.set(output);
+            outputs.push(output);
+        }).unwrap();
+
+        assert_eq!(state, RunState::Halted);
+        assert_eq!(outputs, vec![2,4,8,16,32,64,128]);
+    }
+
+    #[test]
+    fn test_run_with_channels() {
+        let (input_sender, input_receiver) = channel();
+        let (output_sender, output_receiver) = channel();
+        let mut computer = IntcodeComputer::new();
+        computer.load(&[3,11,1001,11,1,11,4,11,1105,1,0,0]);
+
+        let machine = thread::spawn(move || {
+            let mut input_receiver = input_receiver;
+            let mut output_sender = output_sender;
+            computer.run_with(&mut input_receiver, &mut output_sender)
+        });
+        for value in 0..3 {
+            input_sender.send(value * 10).unwrap();
+            assert_eq!(output_receiver.recv(), Ok(value * 10 + 1));
+        }
+        drop(input_sender);
+
+        assert_eq!(machine.join().unwrap(), Ok(RunState::AwaitingInput));
+    }
+
+    #[test]
+    fn test_run_with_prefers_queued_inputs() {
+        let mut computer = IntcodeComputer::new();
+        let mut outputs = VecDeque::new();
+
+        computer.load(&[3,0,4,0,3,0,4,0,3,0,4,0,99]);
+        computer.push_input(1);
+        let state = computer.run_with(&mut vec![2], &mut outputs).unwrap();
+
+        assert_eq!(state, RunState::AwaitingInput);
+        assert_eq!(outputs, vec![1,2]);
+    }
+
+    #[test]
+    fn test_load_clears_queued_inputs() {
+        let mut computer = IntcodeComputer::new();
+        let program = vec![3,1,99];
+
+        computer.push_input(7);
+        computer.load(&program);
+
+        assert_eq!(computer.resume().unwrap(), (RunState::AwaitingInput, vec![]));
+    }
+
     #[test]
     fn test_program_with_output() {
         let mut computer = IntcodeComputer::new();
         let program = vec![4,2,99];
 
         computer.load(&program);
-        let outputs = computer.run(None);
+        let outputs = computer.run(None).unwrap();
 
         let expected_memory = vec![4,2,99];
         let expected_outputs = vec![99];
@@ -142,7 +931,7 @@ mod tests {
         let program = vec![1,0,0,3,99];
 
         computer.load(&program);
-        computer.run(None);
+        computer.run(None).unwrap();
 
         let expected_memory = vec![1,0,0,2,99];
 
@@ -151,13 +940,13 @@ mod tests {
 
     #[test]
     fn test_program_with_multiple_instructions() {
-        let expected_memory: Vec<isize> = vec![30,1,1,4,2,5,6,0,99];
+        let expected_memory: Vec<i64> = vec![30,1,1,4,2,5,6,0,99];
 
         let mut computer = IntcodeComputer::new();
         let program = vec![1,1,1,4,99,5,6,0,99];
 
         computer.load(&program);
-        computer.run(None);
+        computer.run(None).unwrap();
 
         assert_eq!(computer.memory, expected_memory);
     }
@@ -171,23 +960,257 @@ mod tests {
 
         computer.load(&program);
         let inputs = Some(vec![0]);
-        let outputs = computer.run(inputs);
+        let outputs = computer.run(inputs).unwrap();
 
         assert_eq!(expected_output, outputs[0]);
     }
 
 
+    #[test]
+    fn test_parse_program() {
+        assert_eq!(parse_program::<i64>("1,0, 0,3,-99\n"), Ok(vec![1,0,0,3,-99]));
+        assert_eq!(parse_program::<BigInt>("1,x,3"), Err(ParseWordError { text: "x".to_string() }));
+    }
+
+    #[test]
+    fn test_load_program() {
+        assert_eq!(load_program("input_day_2.txt").map(|program| program[..4].to_vec()), Ok(vec![1,0,0,3]));
+        assert!(load_program("no_such_file.txt").unwrap_err().starts_with("Could not load no_such_file.txt: "));
+        assert_eq!(parse_inputs(&["5".to_string(), "-2".to_string()]), Ok(vec![5,-2]));
+        assert_eq!(parse_inputs(&["5".to_string(), "five".to_string()]), Err("Not a valid input: five".to_string()));
+    }
+
+    #[test]
+    fn test_instruction_budget() {
+        let mut computer = IntcodeComputer::new();
+        computer.set_instruction_budget(Some(3));
+        computer.load(&[1101,1,1,0,1101,2,2,0,1101,3,3,0,99]);
+
+        assert_eq!(computer.resume(), Ok((RunState::Halted, vec![])));
+
+        computer.load(&[1101,1,1,0,1101,2,2,0,1101,3,3,0,1101,4,4,0,99]);
+
+        assert_eq!(computer.resume(), Err(IntcodeError::InstructionBudgetExhausted { budget: 3, address: 12 }));
+        assert_eq!(computer.resume(), Ok((RunState::Halted, vec![])));
+        assert_eq!(computer.memory[0], 8);
+    }
+
+    #[test]
+    fn test_budget_returns_unused_input() {
+        let mut computer = IntcodeComputer::new();
+        computer.set_instruction_budget(Some(1));
+        computer.load(&[104,1,3,0,4,0,99]);
+        computer.push_input(5);
+
+        assert_eq!(computer.run(None), Err(IntcodeError::InstructionBudgetExhausted { budget: 1, address: 2 }));
+        assert_eq!(computer.run(None), Err(IntcodeError::InstructionBudgetExhausted { budget: 1, address: 4 }));
+        assert_eq!(computer.run(None), Ok(vec![5]));
+    }
+
+    #[test]
+    fn test_time_limit() {
+        // Counts forever, so no state ever repeats
+        let mut computer = IntcodeComputer::new();
+        computer.set_time_limit(Some(Duration::from_millis(20)));
+        computer.set_loop_detection(true);
+        computer.load(&[1001,5,1,5,1105,1,0]);
+
+        match computer.run_until_output() {
+            Err(IntcodeError::TimeLimitExceeded { limit, .. }) => assert_eq!(limit, Duration::from_millis(20)),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_loop_detection() {
+        // Flips mem[12] between 0 and 1 forever, outputting it each time
+        let mut computer = IntcodeComputer::new();
+        computer.set_loop_detection(true);
+        computer.load(&[1008,12,0,12,4,12,1105,1,0,99,0,0,0]);
+
+        assert_eq!(computer.run(None), Err(IntcodeError::InfiniteLoop { address: 4, length: 6 }));
+    }
+
+    #[test]
+    fn test_loop_detection_waits_for_inputs() {
+        // Echoes inputs until it reads a zero, then spins forever
+        let mut computer = IntcodeComputer::new();
+        computer.set_loop_detection(true);
+        computer.load(&[3,11,4,11,1005,11,0,1105,1,7,99,0]);
+
+        assert_eq!(computer.run(Some(vec![1,2,0])), Err(IntcodeError::InfiniteLoop { address: 7, length: 1 }));
+        computer.load(&[3,11,4,11,1005,11,0,1105,1,7,99,0]);
+        assert_eq!(computer.resume(), Ok((RunState::AwaitingInput, vec![])));
+    }
+
+    #[test]
+    fn test_day_2_search_with_budget() {
+        let program = parse_program(&fs::read_to_string("input_day_2.txt").unwrap()).unwrap();
+        let mut computer = IntcodeComputer::new();
+        computer.set_instruction_budget(Some(10_000));
+        computer.set_loop_detection(true);
+
+        let (noun, verb) = (0..100).flat_map(|noun| (0..100).map(move |verb| (noun, verb)))
+            .find(|&(noun, verb)| {
+                let mut variation = program.clone();
+                variation[1] = noun;
+                variation[2] = verb;
+                computer.load(&variation);
+                computer.run(None).is_ok() && computer.memory[0] == 19690720
+            })
+            .unwrap();
+
+        assert_eq!(100 * noun + verb, 4559);
+    }
+
+    #[test]
+    fn test_arithmetic_modes() {
+        let program = vec![1102,i64::MAX,3,7,4,7,99,0];
+        let mut computer = IntcodeComputer::new();
+
+        computer.load(&program);
+        assert_eq!(computer.run(None), Err(IntcodeError::ArithmeticOverflow { address: 0 }));
+
+        computer.set_arithmetic(Arithmetic::Wrapping);
+        computer.load(&program);
+        assert_eq!(computer.run(None), Ok(vec![i64::MAX.wrapping_mul(3)]));
+
+        let mut wide: IntcodeComputer<i128> = IntcodeComputer::default();
+        wide.load(&program.iter().map(|&code| code as i128).collect::<Vec<i128>>());
+        assert_eq!(wide.run(None), Ok(vec![i64::MAX as i128 * 3]));
+    }
+
+    const DOUBLE: CustomOpcode = CustomOpcode { code: 20, mnemonic: "DOUBLE", parameter_count: 2, write_parameters: &[1] };
+    const HALT_WITH_CODE: CustomOpcode = CustomOpcode { code: 50, mnemonic: "HALTCODE", parameter_count: 1, write_parameters: &[] };
+
+    fn extended_opcodes() -> OpcodeRegistry {
+        let mut opcodes = OpcodeRegistry::new();
+        opcodes.register(DOUBLE, |context| {
+            let doubled = context.arithmetic().multiply(&context.parameter(0), &2, context.address())?;
+            context.write(doubled)
+        }).unwrap();
+        opcodes.register(HALT_WITH_CODE, |context| {
+            context.output(context.parameter(0));
+            context.halt();
+            Ok(())
+        }).unwrap();
+        opcodes
+    }
+
+    #[test]
+    fn test_custom_opcodes() {
+        let mut computer = IntcodeComputer::new();
+        computer.set_opcodes(extended_opcodes());
+        computer.load(&[120,21,9,4,9,150,3,99,0,0]);
+
+        assert_eq!(computer.resume().unwrap(), (RunState::Halted, vec![42,3]));
+        assert_eq!(computer.position(), 5);
+        assert_eq!(computer.resume().unwrap(), (RunState::Halted, vec![]));
+
+        computer.set_opcodes(OpcodeRegistry::new());
+        computer.load(&[120,21,9,99]);
+        assert_eq!(computer.run(None), Err(IntcodeError::UnknownOpcode { opcode: 120, address: 0 }));
+    }
+
+    #[test]
+    fn test_custom_opcode_without_write_parameter() {
+        let mut opcodes = OpcodeRegistry::new();
+        opcodes.register(CustomOpcode { write_parameters: &[], ..DOUBLE }, |context| context.write(context.parameter(0))).unwrap();
+        let mut computer = IntcodeComputer::new();
+        computer.set_opcodes(opcodes);
+        computer.load(&[104,1,120,21,9,99]);
+
+        let error = computer.run(None).unwrap_err();
+
+        assert_eq!(error, IntcodeError::NoWriteParameter { opcode: 20, address: 2 });
+        assert_eq!(error.to_string(), "Opcode 20 at address 2 has no parameter to write to");
+    }
+
+    #[test]
+    fn test_custom_opcode_with_two_write_parameters() {
+        let swap = CustomOpcode { code: 30, mnemonic: "SWAP", parameter_count: 3, write_parameters: &[0, 1] };
+        let mut opcodes = OpcodeRegistry::new();
+        opcodes.register(swap, |context| {
+            let (left, right) = (context.parameter(0), context.parameter(1));
+            context.write_to(0, right)?;
+            context.write_to(1, left)
+        }).unwrap();
+        let mut computer = IntcodeComputer::new();
+        computer.set_opcodes(opcodes.clone());
+        computer.set_journal(Some(10));
+        computer.load(&[30,7,8,0,4,7,99,11,22]);
+
+        let step = match computer.step().unwrap() {
+            StepOutcome::Executed(step) => step,
+            outcome => panic!("Unexpected outcome: {:?}", outcome),
+        };
+        assert_eq!(step.reads, vec![(0,30)]);
+        assert_eq!(step.writes, vec![(7,22), (8,11)]);
+        assert_eq!(computer.run(None), Ok(vec![22]));
+
+        assert!(computer.rewind_to(0));
+        assert_eq!(computer.memory, vec![30,7,8,0,4,7,99,11,22]);
+
+        opcodes.register(swap, |context| context.write_to(2, 0)).unwrap();
+        computer.set_opcodes(opcodes);
+        assert_eq!(computer.run(None), Err(IntcodeError::NoWriteParameter { opcode: 30, address: 0 }));
+    }
+
+    #[test]
+    fn test_custom_opcodes_are_recorded() {
+        let mut computer = IntcodeComputer::new();
+        computer.set_opcodes(extended_opcodes());
+        computer.set_journal(Some(8));
+        computer.set_profiling(true);
+        computer.load(&[120,21,9,4,9,150,3,99,0,0]);
+
+        let step = match computer.step().unwrap() {
+            StepOutcome::Executed(step) => step,
+            outcome => panic!("Unexpected outcome: {:?}", outcome),
+        };
+        computer.run(None).unwrap();
+
+        assert_eq!(step.opcode, Opcode::Custom(DOUBLE));
+        assert_eq!(step.writes, vec![(9, 42)]);
+        assert_eq!(computer.profile().unwrap().opcode_counts(), vec![(Opcode::Output, 1), (Opcode::Custom(DOUBLE), 1), (Opcode::Custom(HALT_WITH_CODE), 1)]);
+        assert!(computer.rewind_to(0));
+        assert_eq!(computer.memory[9], 0);
+        assert_eq!(computer.run(None).unwrap(), vec![42,3]);
+    }
+
+    #[test]
+    fn test_unknown_opcode_handler() {
+        let program = vec![42,104,1,99];
+        let mut computer = IntcodeComputer::new();
+        let mut opcodes = OpcodeRegistry::new();
+
+        opcodes.set_unknown_handler(|_, _| UnknownOpcodeAction::Skip);
+        computer.set_opcodes(opcodes.clone());
+        computer.load(&program);
+        assert_eq!(computer.run(None).unwrap(), vec![1]);
+
+        opcodes.set_unknown_handler(|&code, _| match code {
+            42 => UnknownOpcodeAction::Halt,
+            _ => UnknownOpcodeAction::Fail,
+        });
+        computer.set_opcodes(opcodes.clone());
+        computer.load(&program);
+        assert_eq!(computer.resume().unwrap(), (RunState::Halted, vec![]));
+        computer.load(&[43]);
+        assert_eq!(computer.run(None), Err(IntcodeError::UnknownOpcode { opcode: 43, address: 0 }));
+    }
+
     #[test]
     fn test_day_2_part_1() {
         let mut computer = IntcodeComputer::new();
 
-        let mut day_2_input: Vec<isize> = fs::read_to_string("input_day_2.txt").unwrap().trim().split(",").map(|code| code.parse::<isize>().unwrap()).collect();
+        let mut day_2_input: Vec<i64> = fs::read_to_string("input_day_2.txt").unwrap().trim().split(",").map(|code| code.parse::<i64>().unwrap()).collect();
 
         day_2_input[1] = 12;
         day_2_input[2] = 2;
 
         computer.load(&day_2_input);
-        computer.run(None);
+        computer.run(None).unwrap();
 
         assert_eq!(5434663, computer.memory[0]);
     }
@@ -196,7 +1219,7 @@ mod tests {
     fn test_day_2_part_2() {
         let mut computer = IntcodeComputer::new();
 
-        let day_2_input: Vec<isize> = fs::read_to_string("input_day_2.txt").unwrap().trim().split(",").map(|code| code.parse::<isize>().unwrap()).collect();
+        let day_2_input: Vec<i64> = fs::read_to_string("input_day_2.txt").unwrap().trim().split(",").map(|code| code.parse::<i64>().unwrap()).collect();
 
         let mut noun_variation = 0;
         let mut verb_variation = 0;
@@ -209,7 +1232,7 @@ mod tests {
                 program_variation[2] = verb_variation;
             
                 computer.load(&program_variation);
-                computer.run(None);
+                computer.run(None).unwrap();
 
                 if computer.memory[0] == 19690720 {
                     break 'noun;
@@ -226,29 +1249,313 @@ mod tests {
         assert_eq!(verb_variation, 59);
     }
 
+    #[test]
+    fn test_step_back() {
+        let mut computer = IntcodeComputer::new();
+        computer.set_journal(Some(10));
+        computer.load(&[3,0,109,5,1001,0,1,0,99]);
+        computer.push_input(7);
+
+        assert_eq!(computer.resume().unwrap(), (RunState::Halted, vec![]));
+        assert_eq!(computer.memory, vec![8,0,109,5,1001,0,1,0,99]);
+
+        let entry = computer.step_back().unwrap();
+        assert_eq!(entry.writes, vec![(0, 7, 8)]);
+        assert_eq!(computer.position(), 4);
+        assert_eq!(computer.relative_base(), 5);
+
+        computer.step_back();
+        computer.step_back();
+        assert_eq!(computer.position(), 0);
+        assert_eq!(computer.relative_base(), 0);
+        assert_eq!(computer.memory, vec![3,0,109,5,1001,0,1,0,99]);
+        assert_eq!(computer.step_back(), None);
+
+        assert_eq!(computer.resume().unwrap(), (RunState::Halted, vec![]));
+        assert_eq!(computer.memory[0], 8);
+    }
+
+    #[test]
+    fn test_rewind_replays_outputs() {
+        let mut computer = IntcodeComputer::new();
+        computer.set_journal(Some(100));
+        computer.load(&[3,11,1001,11,1,11,4,11,1105,1,0,0]);
+
+        assert_eq!(computer.run(Some(vec![1,5])).unwrap(), vec![2,6]);
+        assert_eq!(computer.journal().unwrap().steps(), 8);
+
+        assert!(computer.rewind_to(5));
+        assert!(!computer.rewind_to(6));
+        assert_eq!(computer.resume().unwrap(), (RunState::AwaitingInput, vec![6]));
+    }
+
+    #[test]
+    fn test_journal_capacity_bounds_rewind() {
+        let mut computer = IntcodeComputer::new();
+        computer.set_journal(Some(2));
+        computer.load(&[1101,1,1,0,1101,2,2,0,1101,3,3,0,99]);
+
+        computer.run(None).unwrap();
+
+        assert!(!computer.rewind_to(0));
+        assert_eq!(computer.memory[0], 6);
+        assert!(computer.rewind_to(1));
+        assert_eq!(computer.memory[0], 2);
+        assert_eq!(computer.position(), 4);
+    }
+
+    #[test]
+    fn test_day_5_walk_back_to_diagnostic_code() {
+        let program = parse_program(&fs::read_to_string("input_day_5.txt").unwrap()).unwrap();
+        let mut computer = IntcodeComputer::new();
+        computer.set_journal(Some(1000));
+        computer.load(&program);
+
+        let outputs = computer.run(Some(vec![1])).unwrap();
+        let output_entry = computer.journal().unwrap().last_output().unwrap().clone();
+        assert_eq!(output_entry.output, outputs.last().copied());
+
+        assert!(computer.rewind_to(output_entry.step));
+        let instruction = computer.instruction().unwrap();
+        assert_eq!(instruction.opcode, Opcode::Output);
+
+        let source = instruction.parameter_positions()[0];
+        let producer = computer.journal().unwrap().last_write_before(source, output_entry.step).unwrap().clone();
+        assert_eq!(producer.writes.iter().map(|(address, _, new_value)| (*address, *new_value)).collect::<Vec<(usize, i64)>>(), vec![(source, 6745903)]);
+
+        assert!(computer.rewind_to(producer.step));
+        assert_eq!(computer.position(), producer.position);
+        let step = match computer.step().unwrap() {
+            StepOutcome::Executed(step) => step,
+            outcome => panic!("Unexpected outcome: {:?}", outcome),
+        };
+        assert_eq!(step.writes, vec![(source, 6745903)]);
+    }
+
     #[test]
     fn test_day_5_part_1() {
         let mut computer = IntcodeComputer::new();
-        let expected_final_output: isize = 6745903;
-        let day_5_input: Vec<isize> = fs::read_to_string("input_day_5.txt").unwrap().trim().split(",").map(|code| code.parse::<isize>().unwrap()).collect();
-        let inputs: Option<Vec<isize>> = Some(vec![1]);
+        let expected_final_output: i64 = 6745903;
+        let day_5_input: Vec<i64> = fs::read_to_string("input_day_5.txt").unwrap().trim().split(",").map(|code| code.parse::<i64>().unwrap()).collect();
+        let inputs: Option<Vec<i64>> = Some(vec![1]);
 
         computer.load(&day_5_input);
-        let outputs = computer.run(inputs);
+        let outputs = computer.run(inputs).unwrap();
 
         assert_eq!(expected_final_output, *outputs.last().unwrap());
     }
 
+    #[test]
+    fn test_day_5_with_decode_cache() {
+        let mut computer = IntcodeComputer::new();
+        let day_5_input: Vec<i64> = fs::read_to_string("input_day_5.txt").unwrap().trim().split(",").map(|code| code.parse::<i64>().unwrap()).collect();
+
+        computer.set_decode_cache(true);
+        computer.load(&day_5_input);
+        let part_1_outputs = computer.run(Some(vec![1])).unwrap();
+        computer.load(&day_5_input);
+        let part_2_outputs = computer.run(Some(vec![5])).unwrap();
+
+        assert_eq!(*part_1_outputs.last().unwrap(), 6745903);
+        assert_eq!(part_2_outputs, vec![9168267]);
+    }
+
     #[test]
     fn test_day_5_part_2() {
         let mut computer = IntcodeComputer::new();
-        let expected_final_output: isize = 9168267;
-        let day_5_input: Vec<isize> = fs::read_to_string("input_day_5.txt").unwrap().trim().split(",").map(|code| code.parse::<isize>().unwrap()).collect();
-        let inputs: Option<Vec<isize>> = Some(vec![5]);
+        let expected_final_output: i64 = 9168267;
+        let day_5_input: Vec<i64> = fs::read_to_string("input_day_5.txt").unwrap().trim().split(",").map(|code| code.parse::<i64>().unwrap()).collect();
+        let inputs: Option<Vec<i64>> = Some(vec![5]);
 
         computer.load(&day_5_input);
-        let outputs = computer.run(inputs);
+        let outputs = computer.run(inputs).unwrap();
 
         assert_eq!(expected_final_output, *outputs.last().unwrap());
     }
+
+    fn run_feedback_loop(program: &[i64], phases: &[i64]) -> i64 {
+        let mut amps: Vec<IntcodeComputer> = phases.iter().map(|phase| {
+            let mut amp = IntcodeComputer::new();
+            amp.load(program);
+            amp.push_input(*phase);
+            amp
+        }).collect();
+
+        let mut signal = 0;
+        let mut amp_number = 0;
+        loop {
+            amps[amp_number].push_input(signal);
+            match amps[amp_number].run_until_output().unwrap() {
+                RunState::Output(output) => signal = output,
+                _ => return signal,
+            }
+            amp_number = (amp_number + 1) % amps.len();
+        }
+    }
+
+    pub(crate) fn permutations(values: Vec<i64>) -> Vec<Vec<i64>> {
+        if values.len() <= 1 {
+            return vec![values];
+        }
+        let mut result = vec![];
+        for index in 0..values.len() {
+            let mut rest = values.clone();
+            let first = rest.remove(index);
+            for mut permutation in permutations(rest) {
+                permutation.insert(0, first);
+                result.push(permutation);
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_day_7_part_2() {
+        let day_7_input: Vec<i64> = fs::read_to_string("input_day_7.txt").unwrap().trim().split(",").map(|code| code.parse::<i64>().unwrap()).collect();
+
+        let highest_thrust = permutations(vec![5,6,7,8,9]).iter()
+            .map(|phases| run_feedback_loop(&day_7_input, phases))
+            .max()
+            .unwrap();
+
+        assert_eq!(highest_thrust, 17279674);
+    }
+
+    #[test]
+    fn test_day_9_part_1() {
+        let mut computer = IntcodeComputer::new();
+        let day_9_input: Vec<i64> = fs::read_to_string("input_day_9.txt").unwrap().trim().split(",").map(|code| code.parse::<i64>().unwrap()).collect();
+
+        computer.load(&day_9_input);
+        let outputs = computer.run(Some(vec![1])).unwrap();
+
+        assert_eq!(outputs, vec![2714716640]);
+    }
+
+    #[test]
+    fn test_day_9_part_2() {
+        let mut computer = IntcodeComputer::new();
+        let day_9_input: Vec<i64> = fs::read_to_string("input_day_9.txt").unwrap().trim().split(",").map(|code| code.parse::<i64>().unwrap()).collect();
+
+        computer.load(&day_9_input);
+        let outputs = computer.run(Some(vec![2])).unwrap();
+
+        assert_eq!(outputs, vec![58879]);
+    }
+
+    #[test]
+    fn test_wide_multiplication() {
+        // Squares 10^30, which needs 200 bits
+        let program = "1002,7,1000000000000000000000000000000,7,4,7,99,1000000000000000000000000000000";
+        let mut wide: IntcodeComputer<i128> = IntcodeComputer::default();
+        let mut big: IntcodeComputer<BigInt> = IntcodeComputer::default();
+        wide.load(&parse_program(program).unwrap());
+        big.load(&parse_program(program).unwrap());
+
+        assert_eq!(wide.run(None), Err(IntcodeError::ArithmeticOverflow { address: 0 }));
+        assert_eq!(big.run(None).unwrap()[0].to_string(), format!("1{}", "0".repeat(60)));
+    }
+
+    // Runs the same programs with each word type
+    macro_rules! word_suite {
+        ($module:ident, $word:ty) => {
+            mod $module {
+                use super::*;
+
+                fn program(day: usize) -> Vec<$word> {
+                    parse_program(&fs::read_to_string(format!("input_day_{}.txt", day)).unwrap()).unwrap()
+                }
+
+                fn words(values: &[i64]) -> Vec<$word> {
+                    values.iter().map(|&value| <$word>::from_i64(value)).collect()
+                }
+
+                fn run(program: &[$word], inputs: &[i64]) -> Vec<$word> {
+                    let mut computer: IntcodeComputer<$word> = IntcodeComputer::default();
+                    computer.load(program);
+                    computer.run(Some(words(inputs))).unwrap()
+                }
+
+                #[test]
+                fn test_day_2_part_1() {
+                    let mut program = program(2);
+                    program[1] = <$word>::from_i64(12);
+                    program[2] = <$word>::from_i64(2);
+                    let mut computer: IntcodeComputer<$word> = IntcodeComputer::default();
+
+                    computer.load(&program);
+                    computer.run(None).unwrap();
+
+                    assert_eq!(computer.memory[0], <$word>::from_i64(5434663));
+                }
+
+                #[test]
+                fn test_day_5() {
+                    assert_eq!(run(&program(5), &[1]).last(), Some(&<$word>::from_i64(6745903)));
+                    assert_eq!(run(&program(5), &[5]), words(&[9168267]));
+                }
+
+                #[test]
+                fn test_day_7_part_2() {
+                    let program = program(7);
+
+                    let highest_thrust = permutations(vec![5,6,7,8,9]).iter()
+                        .map(|phases| {
+                            let mut network = network::Network::new(network::Topology::Ring);
+                            for (index, phase) in phases.iter().enumerate() {
+                                let inputs = match index {
+                                    0 => vec![*phase, 0],
+                                    _ => vec![*phase],
+                                };
+                                network.add_machine(&program, words(&inputs));
+                            }
+                            network.run().unwrap().pop().unwrap().outputs.pop().unwrap()
+                        })
+                        .max()
+                        .unwrap();
+
+                    assert_eq!(highest_thrust, <$word>::from_i64(17279674));
+                }
+
+                #[test]
+                fn test_day_9() {
+                    let program = program(9);
+
+                    assert_eq!(run(&program, &[1]), words(&[2714716640]));
+                    assert_eq!(run(&program, &[2]), words(&[58879]));
+                }
+
+                #[test]
+                fn test_day_9_examples() {
+                    let quine = words(&[109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99]);
+
+                    assert_eq!(run(&quine, &[]), quine);
+                    assert_eq!(run(&words(&[1102,34915192,34915192,7,4,7,99,0]), &[]), words(&[1219070632396864]));
+                    assert_eq!(run(&words(&[104,1125899906842624,99]), &[]), words(&[1125899906842624]));
+                }
+
+                #[test]
+                fn test_step_back_and_snapshot() {
+                    let mut computer: IntcodeComputer<$word> = IntcodeComputer::default();
+                    computer.set_journal(Some(16));
+                    computer.load(&program(5));
+                    computer.push_input(<$word>::from_i64(1));
+                    for _ in 0..10 {
+                        computer.step().unwrap();
+                    }
+                    let snapshot = computer.snapshot();
+
+                    while computer.step_back().is_some() {}
+                    computer.restore(&snapshot);
+
+                    assert_eq!(computer.position(), snapshot.position);
+                    assert_eq!(computer.run(None).unwrap().last(), Some(&<$word>::from_i64(6745903)));
+                }
+            }
+        };
+    }
+
+    word_suite!(i64_words, i64);
+    word_suite!(i128_words, i128);
+    word_suite!(big_words, BigInt);
 }
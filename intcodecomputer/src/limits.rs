@@ -0,0 +1,177 @@
+use crate::memory::PagedMemory;
+use crate::word::Word;
+use crate::IntcodeError;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+// Reading the clock every instruction would dominate tight loops
+const CLOCK_CHECK_INTERVAL: usize = 1024;
+
+// Enforces the instruction budget and time limit over one call that runs the computer
+pub(crate) struct RunGuard {
+    executed: usize,
+    budget: Option<usize>,
+    deadline: Option<(Instant, Duration)>,
+}
+
+impl RunGuard {
+    pub(crate) fn new(budget: Option<usize>, time_limit: Option<Duration>) -> RunGuard {
+        RunGuard {
+            executed: 0,
+            budget,
+            deadline: time_limit.map(|limit| (Instant::now() + limit, limit)),
+        }
+    }
+
+    // Called before each instruction is executed
+    pub(crate) fn check<W: Word>(&mut self, address: usize) -> Result<(), IntcodeError<W>> {
+        if let Some(budget) = self.budget {
+            if self.executed >= budget {
+                return Err(IntcodeError::InstructionBudgetExhausted { budget, address });
+            }
+        }
+        if let Some((deadline, limit)) = self.deadline {
+            if self.executed % CLOCK_CHECK_INTERVAL == 0 && Instant::now() >= deadline {
+                return Err(IntcodeError::TimeLimitExceeded { limit, address });
+            }
+        }
+        self.executed += 1;
+        Ok(())
+    }
+}
+
+struct SavedState<W: Word> {
+    position: usize,
+    relative_base: usize,
+    hash: u64,
+    memory: PagedMemory<W>,
+}
+
+// Brent's cycle detection over whole machine states. A running hash of memory keeps each comparison cheap,
+// and a full memory comparison confirms a match, so a reported loop is certain. Consuming an input
+// starts the search over since the machine's future then depends on more than its state.
+pub(crate) struct LoopDetector<W: Word> {
+    hash: u64,
+    saved: Option<SavedState<W>>,
+    steps: usize,
+    power: usize,
+}
+
+impl<W: Word> LoopDetector<W> {
+    pub(crate) fn new() -> LoopDetector<W> {
+        LoopDetector {
+            hash: 0,
+            saved: None,
+            steps: 0,
+            power: 1,
+        }
+    }
+
+    pub(crate) fn reset(&mut self, memory: &PagedMemory<W>) {
+        self.hash = memory.image().iter().enumerate()
+            .map(|(address, value)| (address, value.clone()))
+            .chain(memory.sparse_cells())
+            .fold(0, |hash, (address, value)| hash ^ cell_hash(address, &value));
+        self.forget();
+    }
+
+    pub(crate) fn record(&mut self, writes: &[(usize, W, W)], consumed_input: bool) {
+        for (address, old_value, new_value) in writes {
+            self.hash ^= cell_hash(*address, old_value) ^ cell_hash(*address, new_value);
+        }
+        if consumed_input {
+            self.forget();
+        }
+    }
+
+    // The loop's length in instructions once the machine is back in a state it was in before
+    pub(crate) fn check(&mut self, position: usize, relative_base: usize, memory: &PagedMemory<W>) -> Option<usize> {
+        if let Some(saved) = &self.saved {
+            if saved.position == position && saved.relative_base == relative_base && saved.hash == self.hash && saved.memory == *memory {
+                return Some(self.steps);
+            }
+        }
+        if self.saved.is_none() || self.steps == self.power {
+            if self.saved.is_some() {
+                self.power *= 2;
+            }
+            self.saved = Some(SavedState { position, relative_base, hash: self.hash, memory: memory.clone() });
+            self.steps = 0;
+        }
+        self.steps += 1;
+        None
+    }
+
+    fn forget(&mut self) {
+        self.saved = None;
+        self.steps = 0;
+        self.power = 1;
+    }
+}
+
+// Zero cells hash to zero so unwritten memory never needs visiting
+fn cell_hash<W: Word>(address: usize, value: &W) -> u64 {
+    if value.is_zero() {
+        return 0;
+    }
+    let mut hasher = DefaultHasher::new();
+    (address, value).hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget() {
+        let mut guard = RunGuard::new(Some(2), None);
+
+        assert_eq!(guard.check::<i64>(0), Ok(()));
+        assert_eq!(guard.check::<i64>(4), Ok(()));
+        assert_eq!(guard.check::<i64>(8), Err(IntcodeError::InstructionBudgetExhausted { budget: 2, address: 8 }));
+    }
+
+    #[test]
+    fn test_elapsed_time_limit() {
+        let mut guard = RunGuard::new(None, Some(Duration::from_secs(0)));
+
+        assert_eq!(guard.check::<i64>(3), Err(IntcodeError::TimeLimitExceeded { limit: Duration::from_secs(0), address: 3 }));
+    }
+
+    #[test]
+    fn test_hash_tracks_writes() {
+        let memory: PagedMemory = PagedMemory::from(&[1,2,3][..]);
+        let mut detector = LoopDetector::new();
+        detector.reset(&memory);
+        let initial_hash = detector.hash;
+
+        detector.record(&[(1, 2, 5)], false);
+        assert_ne!(detector.hash, initial_hash);
+        detector.record(&[(1, 5, 2)], false);
+        assert_eq!(detector.hash, initial_hash);
+    }
+
+    #[test]
+    fn test_detects_repeated_state() {
+        let memory: PagedMemory = PagedMemory::from(&[1105,1,0][..]);
+        let mut detector = LoopDetector::new();
+        detector.reset(&memory);
+
+        assert_eq!(detector.check(0, 0, &memory), None);
+        assert_eq!(detector.check(0, 0, &memory), Some(1));
+    }
+
+    #[test]
+    fn test_input_restarts_search() {
+        let memory: PagedMemory = PagedMemory::from(&[3,0,1105,1,0][..]);
+        let mut detector = LoopDetector::new();
+        detector.reset(&memory);
+
+        assert_eq!(detector.check(0, 0, &memory), None);
+        detector.record(&[], true);
+        assert_eq!(detector.check(0, 0, &memory), None);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::error::IntcodeError;
+use crate::word::Word;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Index;
+
+pub const PAGE_SIZE: usize = 1024;
+
+// Unwritten cells read as zero, so memory is conceptually infinite
+pub trait Memory<W: Word = i64> {
+    fn read(&self, address: usize) -> W;
+    fn write(&mut self, address: usize, value: W) -> Result<(), IntcodeError<W>>;
+}
+
+// A plain vector, grown to reach any address written to
+impl<W: Word> Memory<W> for Vec<W> {
+    fn read(&self, address: usize) -> W {
+        self.get(address).cloned().unwrap_or_else(W::zero)
+    }
+
+    fn write(&mut self, address: usize, value: W) -> Result<(), IntcodeError<W>> {
+        if address >= self.len() {
+            self.resize(address + 1, W::zero());
+        }
+        self[address] = value;
+        Ok(())
+    }
+}
+
+// Keeps the program image dense and allocates fixed size pages for anything written beyond it
+#[derive(Clone)]
+pub struct PagedMemory<W: Word = i64> {
+    image: Vec<W>,
+    pages: HashMap<usize, Box<[W]>>,
+    limit: Option<usize>,
+    // Lets unwritten cells be handed out by reference
+    zero: W,
+}
+
+impl<W: Word> Default for PagedMemory<W> {
+    fn default() -> Self {
+        PagedMemory {
+            image: vec![],
+            pages: HashMap::new(),
+            limit: None,
+            zero: W::zero(),
+        }
+    }
+}
+
+impl<W: Word> PagedMemory<W> {
+    pub fn new() -> PagedMemory<W> {
+        PagedMemory::default()
+    }
+
+    // Caps the number of cells the image and pages may take up together
+    pub fn with_limit(limit: Option<usize>) -> PagedMemory<W> {
+        PagedMemory {
+            limit,
+            ..PagedMemory::default()
+        }
+    }
+
+    pub fn load(&mut self, program: &[W]) {
+        self.image = program.to_vec();
+        self.pages.clear();
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    pub fn image(&self) -> &[W] {
+        &self.image
+    }
+
+    pub fn allocated(&self) -> usize {
+        self.image.len() + self.pages.len() * PAGE_SIZE
+    }
+
+    // Addresses outside the image holding a non-zero value, in ascending order
+    pub fn sparse_cells(&self) -> Vec<(usize, W)> {
+        let mut page_numbers: Vec<&usize> = self.pages.keys().collect();
+        page_numbers.sort_unstable();
+
+        page_numbers.into_iter()
+            .flat_map(|page_number| self.pages[page_number].iter().enumerate()
+                .map(move |(offset, value)| (page_number * PAGE_SIZE + offset, value.clone())))
+            .filter(|(address, value)| *address >= self.image.len() && !value.is_zero())
+            .collect()
+    }
+
+    pub fn get(&self, address: usize) -> Option<&W> {
+        match address < self.image.len() {
+            true => self.image.get(address),
+            false => self.pages.get(&(address / PAGE_SIZE)).map(|page| &page[address % PAGE_SIZE]),
+        }
+    }
+}
+
+impl<W: Word> Memory<W> for PagedMemory<W> {
+    fn read(&self, address: usize) -> W {
+        self.get(address).cloned().unwrap_or_else(W::zero)
+    }
+
+    fn write(&mut self, address: usize, value: W) -> Result<(), IntcodeError<W>> {
+        if address < self.image.len() {
+            self.image[address] = value;
+            return Ok(());
+        }
+
+        let page_number = address / PAGE_SIZE;
+        if !self.pages.contains_key(&page_number) {
+            if value.is_zero() {
+                return Ok(());
+            }
+            if let Some(limit) = self.limit {
+                if self.allocated() + PAGE_SIZE > limit {
+                    return Err(IntcodeError::MemoryLimitExceeded { address, limit });
+                }
+            }
+            self.pages.insert(page_number, vec![W::zero(); PAGE_SIZE].into_boxed_slice());
+        }
+        self.pages.get_mut(&page_number).unwrap()[address % PAGE_SIZE] = value;
+        Ok(())
+    }
+}
+
+impl<W: Word> Index<usize> for PagedMemory<W> {
+    type Output = W;
+
+    fn index(&self, address: usize) -> &W {
+        self.get(address).unwrap_or(&self.zero)
+    }
+}
+
+impl<W: Word> From<&[W]> for PagedMemory<W> {
+    fn from(program: &[W]) -> PagedMemory<W> {
+        let mut memory = PagedMemory::new();
+        memory.load(program);
+        memory
+    }
+}
+
+// Compares contents as if both sides were padded with zeros forever
+impl<W: Word> PartialEq<Vec<W>> for PagedMemory<W> {
+    fn eq(&self, other: &Vec<W>) -> bool {
+        // Beyond the image only the sparse cells and the rest of other can be non-zero
+        self.image.iter().enumerate().all(|(address, value)| *value == other.read(address))
+            && self.sparse_cells().iter().all(|(address, value)| *value == other.read(*address))
+            && other.iter().enumerate().skip(self.image.len())
+                .all(|(address, value)| *value == self.read(address))
+    }
+}
+
+impl<W: Word> PartialEq for PagedMemory<W> {
+    fn eq(&self, other: &PagedMemory<W>) -> bool {
+        self.image == other.image && self.sparse_cells() == other.sparse_cells()
+    }
+}
+
+impl<W: Word> fmt::Debug for PagedMemory<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PagedMemory")
+            .field("image", &self.image)
+            .field("sparse_cells", &self.sparse_cells())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BigInt;
+
+    #[test]
+    fn test_vec_grows_on_write() {
+        let mut memory: Vec<i64> = vec![1,2];
+
+        memory.write(4, 7).unwrap();
+
+        assert_eq!(memory.read(10), 0);
+        assert_eq!(memory, vec![1,2,0,0,7]);
+    }
+
+    #[test]
+    fn test_far_write_allocates_one_page() {
+        let mut memory: PagedMemory = PagedMemory::from(&[1,2,3][..]);
+
+        memory.write(1_000_000_000, 5).unwrap();
+        memory.write(1, 9).unwrap();
+
+        assert_eq!(memory.read(1_000_000_000), 5);
+        assert_eq!(memory[1], 9);
+        assert_eq!(memory[999_999_999], 0);
+        assert_eq!(memory.allocated(), 3 + PAGE_SIZE);
+        assert_eq!(memory.sparse_cells(), vec![(1_000_000_000, 5)]);
+    }
+
+    #[test]
+    fn test_zero_write_does_not_allocate() {
+        let mut memory: PagedMemory = PagedMemory::new();
+
+        memory.write(5000, 0).unwrap();
+
+        assert_eq!(memory.allocated(), 0);
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut memory: PagedMemory = PagedMemory::with_limit(Some(PAGE_SIZE + 10));
+        memory.load(&[0; 10]);
+
+        memory.write(PAGE_SIZE * 3, 1).unwrap();
+        memory.write(PAGE_SIZE * 3 + 1, 1).unwrap();
+
+        assert_eq!(memory.write(PAGE_SIZE * 5, 1), Err(IntcodeError::MemoryLimitExceeded { address: PAGE_SIZE * 5, limit: PAGE_SIZE + 10 }));
+        assert_eq!(memory.limit(), Some(PAGE_SIZE + 10));
+    }
+
+    #[test]
+    fn test_equality_ignores_trailing_zeros() {
+        let mut memory: PagedMemory = PagedMemory::from(&[1,2][..]);
+        memory.write(3, 4).unwrap();
+
+        assert_eq!(memory, vec![1,2,0,4]);
+        assert_eq!(memory, vec![1,2,0,4,0]);
+        assert_ne!(memory, vec![1,2]);
+    }
+
+    #[test]
+    fn test_equality_with_far_write() {
+        let mut memory: PagedMemory = PagedMemory::from(&[1,2][..]);
+        memory.write(3_000_000_000, 4).unwrap();
+
+        assert_ne!(memory, vec![1,2]);
+        assert_ne!(memory, vec![1,2,0,5]);
+        assert_eq!(PagedMemory::from(&[1i64,2][..]), vec![1,2,0,0]);
+    }
+
+    #[test]
+    fn test_big_words() {
+        let large: BigInt = format!("1{}", "0".repeat(50)).parse().unwrap();
+        let mut memory: PagedMemory<BigInt> = PagedMemory::new();
+
+        memory.write(5000, large.clone()).unwrap();
+
+        assert_eq!(memory[5000], large);
+        assert_eq!(memory[4999], BigInt::zero());
+        assert_eq!(memory.sparse_cells(), vec![(5000, large)]);
+    }
+}
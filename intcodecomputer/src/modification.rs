@@ -0,0 +1,204 @@
+use crate::disassembler::decode_at;
+use crate::word::Word;
+use crate::{Opcode, Step};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+#[derive(Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy)]
+pub enum ModificationKind {
+    // The cell had already been executed as part of an instruction when it was written
+    AfterExecution,
+    // The cell was written and only then executed
+    BeforeExecution,
+}
+
+impl fmt::Display for ModificationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModificationKind::AfterExecution => write!(f, "after execution"),
+            ModificationKind::BeforeExecution => write!(f, "before execution"),
+        }
+    }
+}
+
+// A cell of the instruction stream written by an instruction, counting every time that happened
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone, Copy)]
+pub struct ModificationSite {
+    pub address: usize,
+    pub writer: usize,
+    pub opcode: Opcode,
+    pub kind: ModificationKind,
+    pub count: usize,
+}
+
+// Executed cells include the operands of an instruction as well as its opcode, so patching an operand counts
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone, Default)]
+pub struct Modifications {
+    executed: BTreeSet<usize>,
+    // Every write to each cell not executed yet, in case it is executed later
+    pending: BTreeMap<usize, Vec<(usize, Opcode)>>,
+    sites: BTreeMap<(usize, usize, ModificationKind), (Opcode, usize)>,
+}
+
+impl Modifications {
+    pub fn new() -> Modifications {
+        Modifications::default()
+    }
+
+    // Returns true when the step wrote over a cell that was already executed
+    pub(crate) fn record<W: Word>(&mut self, step: &Step<W>) -> bool {
+        self.record_execution(step.address, step.opcode.parameter_count() + 1);
+        let mut modified = false;
+        for (address, _) in &step.writes {
+            match self.executed.contains(address) {
+                true => {
+                    self.add_site(*address, step.address, step.opcode, ModificationKind::AfterExecution);
+                    modified = true;
+                },
+                false => self.pending.entry(*address).or_default().push((step.address, step.opcode)),
+            }
+        }
+        modified
+    }
+
+    pub(crate) fn record_halt(&mut self, address: usize) {
+        self.record_execution(address, 1);
+    }
+
+    fn record_execution(&mut self, address: usize, size: usize) {
+        for cell in address..address + size {
+            for (writer, opcode) in self.pending.remove(&cell).unwrap_or_default() {
+                self.add_site(cell, writer, opcode, ModificationKind::BeforeExecution);
+            }
+            self.executed.insert(cell);
+        }
+    }
+
+    fn add_site(&mut self, address: usize, writer: usize, opcode: Opcode, kind: ModificationKind) {
+        self.sites.entry((address, writer, kind)).or_insert((opcode, 0)).1 += 1;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sites.is_empty()
+    }
+
+    // Ordered by the address written
+    pub fn sites(&self) -> Vec<ModificationSite> {
+        self.sites.iter()
+            .map(|(&(address, writer, kind), &(opcode, count))| ModificationSite { address, writer, opcode, kind, count })
+            .collect()
+    }
+
+    // Whether any instruction wrote over the cell as part of the instruction stream
+    pub fn modified(&self, address: usize) -> bool {
+        self.sites.keys().any(|&(written, _, _)| written == address)
+    }
+
+    // Lists each site with the writing instruction as it decodes in the program given
+    pub fn report<W: Word>(&self, program: &[W]) -> String {
+        let mut report = format!("self-modifying writes: {}\n", self.sites.len());
+        for site in self.sites() {
+            let writer = match decode_at(program, site.writer) {
+                Some(line) => line.to_string(),
+                None => format!("{:04}: {}", site.writer, site.opcode.mnemonic()),
+            };
+            report.push_str(&format!("  {:04} written {} by {} ({}x)\n", site.address, site.kind, writer, site.count));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_program, IntcodeComputer};
+    use std::fs;
+
+    fn modifications(program: &[i64], inputs: Vec<i64>) -> Modifications {
+        let mut computer = IntcodeComputer::new();
+        computer.set_modification_tracking(true);
+        computer.load(program);
+        computer.run(Some(inputs)).unwrap();
+        computer.modifications().unwrap().clone()
+    }
+
+    #[test]
+    fn test_write_before_execution() {
+        // Writes the input over the operand of the output instruction that follows
+        let program: Vec<i64> = vec![3,3,104,0,99];
+
+        let sites = modifications(&program, vec![7]).sites();
+
+        assert_eq!(sites, vec![ModificationSite { address: 3, writer: 0, opcode: Opcode::Input, kind: ModificationKind::BeforeExecution, count: 1 }]);
+    }
+
+    #[test]
+    fn test_write_after_execution() {
+        // Counts down by rewriting the immediate operand of its own output instruction
+        let program: Vec<i64> = vec![104,3,1001,1,-1,1,1005,1,0,99];
+
+        let modifications = modifications(&program, vec![]);
+
+        assert_eq!(modifications.sites(), vec![
+            ModificationSite { address: 1, writer: 2, opcode: Opcode::Add, kind: ModificationKind::AfterExecution, count: 3 },
+        ]);
+        assert!(modifications.modified(1));
+        assert!(!modifications.modified(2));
+        assert_eq!(modifications.report(&program), "self-modifying writes: 1\n  0001 written after execution by 0002: ADD [1], #-1, [1] (3x)\n");
+    }
+
+    #[test]
+    fn test_every_write_before_execution() {
+        // Both the input and the add write the output instruction's operand before it runs
+        let program: Vec<i64> = vec![3,7,1101,0,7,7,104,0,99];
+
+        let sites = modifications(&program, vec![1]).sites();
+
+        assert_eq!(sites, vec![
+            ModificationSite { address: 7, writer: 0, opcode: Opcode::Input, kind: ModificationKind::BeforeExecution, count: 1 },
+            ModificationSite { address: 7, writer: 2, opcode: Opcode::Add, kind: ModificationKind::BeforeExecution, count: 1 },
+        ]);
+    }
+
+    #[test]
+    fn test_data_writes_are_not_reported() {
+        let program: Vec<i64> = vec![3,5,4,5,99,0];
+
+        assert!(modifications(&program, vec![1]).is_empty());
+    }
+
+    #[test]
+    fn test_day_5_patches_operands() {
+        let program = parse_program(&fs::read_to_string("input_day_5.txt").unwrap()).unwrap();
+
+        let modifications = modifications(&program, vec![5]);
+
+        // Cell 0 is reused as scratch space once the input instruction there has run
+        assert_eq!(modifications.report(&program), "\
+self-modifying writes: 3
+  0000 written after execution by 0284: ADD #294, #0, [0] (1x)
+  0000 written after execution by 0304: ADD #314, #0, [0] (1x)
+  0006 written before execution by 0002: ADD [225], [6], [6] (1x)
+");
+    }
+
+    #[test]
+    fn test_decode_cache_sees_rewritten_opcode() {
+        // The first pass outputs then rewrites its own opcode from output to halt before jumping back
+        let program: Vec<i64> = vec![104,5,1101,0,99,0,1105,1,0];
+        let mut computer = IntcodeComputer::new();
+        computer.set_decode_cache(true);
+        computer.set_modification_tracking(true);
+        computer.load(&program);
+
+        assert_eq!(computer.run(None).unwrap(), vec![5]);
+        assert_eq!(computer.modifications().unwrap().sites()[0].kind, ModificationKind::AfterExecution);
+    }
+}
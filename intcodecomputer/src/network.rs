@@ -0,0 +1,313 @@
+use crate::word::Word;
+use crate::{InputSource, IntcodeComputer, IntcodeError, RunState};
+
+use std::error::Error;
+use std::fmt;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub enum Topology {
+    // Each machine feeds the next one added
+    Pipeline,
+    // A pipeline whose last machine feeds back into the first
+    Ring,
+    // Explicit (from, to) links between machine indexes
+    Graph(Vec<(usize, usize)>),
+}
+
+impl Topology {
+    pub fn links(&self, machine_count: usize) -> Vec<(usize, usize)> {
+        match self {
+            Topology::Pipeline => (1..machine_count).map(|to| (to - 1, to)).collect(),
+            Topology::Ring if machine_count == 0 => vec![],
+            Topology::Ring => (0..machine_count).map(|from| (from, (from + 1) % machine_count)).collect(),
+            Topology::Graph(links) => links.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum NetworkError {
+    MissingMachine { from: usize, to: usize },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::MissingMachine { from, to } => write!(f, "Link from {} to {} refers to a missing machine", from, to),
+        }
+    }
+}
+
+impl Error for NetworkError {}
+
+pub struct MachineResult<W: Word = i64> {
+    pub state: Result<RunState<W>, IntcodeError<W>>,
+    pub outputs: Vec<W>,
+    pub computer: IntcodeComputer<W>,
+}
+
+struct Machine<W: Word> {
+    computer: IntcodeComputer<W>,
+    inputs: Vec<W>,
+}
+
+#[derive(Default)]
+struct Waiting {
+    running: usize,
+    blocked: usize,
+    // Values sent but not received yet, which a blocked machine may just not have woken up to take
+    in_flight: usize,
+    // Set once every running machine was blocked with nothing in flight, after which none can be sent anything
+    stalled: bool,
+}
+
+impl Waiting {
+    fn check_stalled(&mut self) -> bool {
+        self.stalled |= self.running > 0 && self.blocked == self.running && self.in_flight == 0;
+        self.stalled
+    }
+}
+
+#[derive(Default)]
+struct Coordinator {
+    waiting: Mutex<Waiting>,
+    wake: Condvar,
+}
+
+// Feeds a machine from its channel, blocking while the channel is empty unless every running machine is
+// blocked too
+struct NetworkInput<W: Word> {
+    receiver: Receiver<W>,
+    coordinator: Arc<Coordinator>,
+}
+
+impl<W: Word> InputSource<W> for NetworkInput<W> {
+    fn next_input(&mut self) -> Option<W> {
+        let mut waiting = self.coordinator.waiting.lock().unwrap();
+        loop {
+            // Senders send under the same lock, so nothing can arrive between this and the wait
+            match self.receiver.try_recv() {
+                Ok(input) => {
+                    waiting.in_flight -= 1;
+                    return Some(input);
+                },
+                Err(TryRecvError::Disconnected) => return None,
+                Err(TryRecvError::Empty) => (),
+            }
+            waiting.blocked += 1;
+            if waiting.check_stalled() {
+                waiting.blocked -= 1;
+                self.coordinator.wake.notify_all();
+                return None;
+            }
+            waiting = self.coordinator.wake.wait(waiting).unwrap();
+            waiting.blocked -= 1;
+        }
+    }
+}
+
+// Runs every machine on its own thread, passing outputs along the topology's links over channels.
+// A machine stops once it halts, fails, or is waiting on an input no remaining machine can send, including when
+// every machine still running is waiting on another.
+pub struct Network<W: Word = i64> {
+    topology: Topology,
+    machines: Vec<Machine<W>>,
+}
+
+impl<W: Word> Network<W> {
+    pub fn new(topology: Topology) -> Network<W> {
+        Network {
+            topology,
+            machines: vec![],
+        }
+    }
+
+    pub fn add_machine(&mut self, program: &[W], inputs: Vec<W>) -> usize {
+        let mut computer = IntcodeComputer::default();
+        computer.load(program);
+        self.add_computer(computer, inputs)
+    }
+
+    pub fn add_computer(&mut self, computer: IntcodeComputer<W>, inputs: Vec<W>) -> usize {
+        self.machines.push(Machine { computer, inputs });
+        self.machines.len() - 1
+    }
+
+    pub fn run(self) -> Result<Vec<MachineResult<W>>, NetworkError> {
+        let machine_count = self.machines.len();
+        let (senders, receivers): (Vec<Sender<W>>, Vec<Receiver<W>>) = (0..machine_count).map(|_| channel()).unzip();
+
+        let mut targets: Vec<Vec<Sender<W>>> = (0..machine_count).map(|_| vec![]).collect();
+        for (from, to) in self.topology.links(machine_count) {
+            if from >= machine_count || to >= machine_count {
+                return Err(NetworkError::MissingMachine { from, to });
+            }
+            targets[from].push(senders[to].clone());
+        }
+        for (machine, sender) in self.machines.iter().zip(&senders) {
+            for input in &machine.inputs {
+                sender.send(input.clone()).unwrap();
+            }
+        }
+        // Only the machines hold senders from here on, so a receiver disconnects once all of its feeders stop
+        drop(senders);
+
+        let coordinator = Arc::new(Coordinator::default());
+        {
+            let mut waiting = coordinator.waiting.lock().unwrap();
+            waiting.running = machine_count;
+            waiting.in_flight = self.machines.iter().map(|machine| machine.inputs.len()).sum();
+        }
+        let handles: Vec<thread::JoinHandle<MachineResult<W>>> = self.machines.into_iter().zip(receivers).zip(targets)
+            .map(|((machine, receiver), targets)| {
+                let coordinator = Arc::clone(&coordinator);
+                thread::spawn(move || {
+                    let mut computer = machine.computer;
+                    let mut outputs = vec![];
+                    let mut input = NetworkInput { receiver, coordinator: Arc::clone(&coordinator) };
+                    let state = computer.run_with(&mut input, &mut |output: W| {
+                        let mut waiting = coordinator.waiting.lock().unwrap();
+                        // A target that has already stopped has dropped its receiver, so the send fails
+                        for target in &targets {
+                            if target.send(output.clone()).is_ok() {
+                                waiting.in_flight += 1;
+                            }
+                        }
+                        coordinator.wake.notify_all();
+                        outputs.push(output);
+                    });
+
+                    let mut waiting = coordinator.waiting.lock().unwrap();
+                    waiting.in_flight -= input.receiver.try_iter().count();
+                    // Disconnects this machine from its feeders and its targets from it
+                    drop(input);
+                    drop(targets);
+                    waiting.running -= 1;
+                    waiting.check_stalled();
+                    coordinator.wake.notify_all();
+                    drop(waiting);
+                    MachineResult { state, outputs, computer }
+                })
+            })
+            .collect();
+
+        Ok(handles.into_iter().map(|handle| handle.join().unwrap()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_program;
+    use crate::tests::permutations;
+    use std::fs;
+
+    fn amplify(program: &[i64], phases: &[i64]) -> i64 {
+        let mut network = Network::new(Topology::Ring);
+        for (index, phase) in phases.iter().enumerate() {
+            let inputs = match index {
+                0 => vec![*phase, 0],
+                _ => vec![*phase],
+            };
+            network.add_machine(program, inputs);
+        }
+
+        let results = network.run().unwrap();
+
+        *results.last().unwrap().outputs.last().unwrap()
+    }
+
+    #[test]
+    fn test_links() {
+        assert_eq!(Topology::Pipeline.links(3), vec![(0,1),(1,2)]);
+        assert_eq!(Topology::Ring.links(3), vec![(0,1),(1,2),(2,0)]);
+        assert_eq!(Topology::Ring.links(0), vec![]);
+        assert_eq!(Topology::Graph(vec![(2,0)]).links(3), vec![(2,0)]);
+    }
+
+    #[test]
+    fn test_pipeline() {
+        // Adds one to every input
+        let program: Vec<i64> = vec![3,11,1001,11,1,11,4,11,1105,1,0,0];
+        let mut network = Network::new(Topology::Pipeline);
+        network.add_machine(&program, vec![1,10]);
+        network.add_machine(&program, vec![]);
+        network.add_machine(&program, vec![]);
+
+        let results = network.run().unwrap();
+
+        assert_eq!(results[0].outputs, vec![2,11]);
+        assert_eq!(results[2].outputs, vec![4,13]);
+        assert!(results.iter().all(|result| result.state == Ok(RunState::AwaitingInput)));
+    }
+
+    #[test]
+    fn test_graph_fans_out() {
+        let echo: Vec<i64> = vec![3,7,4,7,1105,1,0,0];
+        let mut network = Network::new(Topology::Graph(vec![(0,1),(0,2)]));
+        network.add_machine(&echo, vec![5]);
+        network.add_machine(&echo, vec![]);
+        network.add_machine(&echo, vec![]);
+
+        let results = network.run().unwrap();
+
+        assert_eq!(results[1].outputs, vec![5]);
+        assert_eq!(results[2].outputs, vec![5]);
+    }
+
+    #[test]
+    fn test_missing_machine() {
+        let mut network: Network = Network::new(Topology::Graph(vec![(0,1),(1,3)]));
+        network.add_machine(&[99], vec![]);
+        network.add_machine(&[99], vec![]);
+
+        let error = network.run().err().unwrap();
+
+        assert_eq!(error, NetworkError::MissingMachine { from: 1, to: 3 });
+        assert_eq!(error.to_string(), "Link from 1 to 3 refers to a missing machine");
+    }
+
+    #[test]
+    fn test_ring_waiting_on_itself() {
+        let echo: Vec<i64> = vec![3,7,4,7,1105,1,0,0];
+        let mut network = Network::new(Topology::Ring);
+        network.add_machine(&echo, vec![]);
+        network.add_machine(&echo, vec![]);
+
+        let results = network.run().unwrap();
+
+        assert!(results.iter().all(|result| result.state == Ok(RunState::AwaitingInput) && result.outputs.is_empty()));
+    }
+
+    #[test]
+    fn test_errors_are_collected() {
+        let mut network: Network = Network::new(Topology::Pipeline);
+        network.add_machine(&[104,1,42], vec![]);
+        network.add_machine(&[3,0,4,0,99], vec![]);
+
+        let results = network.run().unwrap();
+
+        assert_eq!(results[0].state, Err(IntcodeError::UnknownOpcode { opcode: 42, address: 2 }));
+        assert_eq!(results[0].outputs, vec![1]);
+        assert_eq!(results[1].state, Ok(RunState::Halted));
+        assert_eq!(results[1].outputs, vec![1]);
+    }
+
+    #[test]
+    fn test_day_7_feedback_loop() {
+        let program = parse_program(&fs::read_to_string("input_day_7.txt").unwrap()).unwrap();
+
+        let highest_thrust = permutations(vec![5,6,7,8,9]).iter()
+            .map(|phases| amplify(&program, phases))
+            .max()
+            .unwrap();
+
+        assert_eq!(highest_thrust, 17279674);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::word::Word;
+use crate::{IntcodeComputer, IntcodeError, RunState};
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+pub const NAT_ADDRESS: usize = 255;
+// A machine may poll more than once before acting on what it was sent, so one quiet round is not enough
+const IDLE_ROUNDS: usize = 2;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub struct Packet<W: Word = i64> {
+    pub source: usize,
+    pub destination: usize,
+    pub x: W,
+    pub y: W,
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub enum PacketEvent<W: Word = i64> {
+    // Includes packets sent to the NAT
+    Sent(Packet<W>),
+    // Sent to an address with no machine or NAT behind it, or to a machine that has halted
+    Dropped(Packet<W>),
+    // The NAT's last packet, sent on to machine 0 because the network went idle
+    Resent(Packet<W>),
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct MachineError<W: Word = i64> {
+    pub address: usize,
+    pub error: IntcodeError<W>,
+}
+
+impl<W: Word> fmt::Display for MachineError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Machine {} failed: {}", self.address, self.error)
+    }
+}
+
+impl<W: Word> Error for MachineError<W> {}
+
+struct PacketMachine<W: Word> {
+    computer: IntcodeComputer<W>,
+    queue: VecDeque<W>,
+    // Outputs that do not make up a whole packet yet
+    outputs: Vec<W>,
+    halted: bool,
+}
+
+// Unlike Network this runs every machine on the calling thread, taking turns in address order, so the same
+// programs always exchange the same packets in the same order. On its turn a machine is given everything in its
+// queue, or -1 if the queue is empty, and runs until it next asks for input.
+pub struct PacketNetwork<W: Word = i64> {
+    machines: Vec<PacketMachine<W>>,
+    nat: Option<Packet<W>>,
+    idle_rounds: usize,
+    rounds: usize,
+}
+
+impl<W: Word> PacketNetwork<W> {
+    pub fn new(program: &[W], size: usize) -> PacketNetwork<W> {
+        let computers = (0..size)
+            .map(|_| {
+                let mut computer = IntcodeComputer::default();
+                computer.load(program);
+                computer
+            })
+            .collect();
+        PacketNetwork::from_computers(computers)
+    }
+
+    // Each computer's address is its index, which is queued as its first input
+    pub fn from_computers(computers: Vec<IntcodeComputer<W>>) -> PacketNetwork<W> {
+        let machines = computers.into_iter().enumerate()
+            .map(|(address, mut computer)| {
+                computer.push_input(W::from_usize(address));
+                PacketMachine { computer, queue: VecDeque::new(), outputs: vec![], halted: false }
+            })
+            .collect();
+        PacketNetwork {
+            machines,
+            nat: None,
+            idle_rounds: 0,
+            rounds: 0,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.machines.len()
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    pub fn computer(&self, address: usize) -> Option<&IntcodeComputer<W>> {
+        self.machines.get(address).map(|machine| &machine.computer)
+    }
+
+    // The packet the NAT would resend if the network went idle now
+    pub fn nat_packet(&self) -> Option<&Packet<W>> {
+        self.nat.as_ref()
+    }
+
+    // Gives every machine one turn, then lets the NAT act if the network has been idle long enough
+    pub fn round(&mut self) -> Result<Vec<PacketEvent<W>>, MachineError<W>> {
+        let mut events = vec![];
+        let mut idle = true;
+        for address in 0..self.machines.len() {
+            let (packets, machine_idle) = self.turn(address)?;
+            idle &= machine_idle;
+            for packet in packets {
+                events.push(self.route(packet));
+            }
+        }
+        self.rounds += 1;
+
+        self.idle_rounds = match idle && self.machines.iter().all(|machine| machine.queue.is_empty()) {
+            true => self.idle_rounds + 1,
+            false => 0,
+        };
+        // A halted machine 0 could never take the packet, so the NAT keeps it rather than resending it forever
+        if self.idle_rounds >= IDLE_ROUNDS && self.machines.first().is_some_and(|machine| !machine.halted) {
+            if let Some(packet) = &self.nat {
+                let packet = Packet { source: NAT_ADDRESS, destination: 0, x: packet.x.clone(), y: packet.y.clone() };
+                self.machines[0].queue.extend(vec![packet.x.clone(), packet.y.clone()]);
+                events.push(PacketEvent::Resent(packet));
+                self.idle_rounds = 0;
+            }
+        }
+        Ok(events)
+    }
+
+    // Runs rounds until stop picks out an event, or gives up after max_rounds returning None
+    pub fn run_until<F>(&mut self, max_rounds: usize, mut stop: F) -> Result<Option<PacketEvent<W>>, MachineError<W>>
+    where
+        F: FnMut(&PacketEvent<W>) -> bool,
+    {
+        for _ in 0..max_rounds {
+            if let Some(event) = self.round()?.into_iter().find(|event| stop(event)) {
+                return Ok(Some(event));
+            }
+        }
+        Ok(None)
+    }
+
+    // Also says whether the machine was idle, having been sent nothing and sent nothing itself
+    fn turn(&mut self, address: usize) -> Result<(Vec<Packet<W>>, bool), MachineError<W>> {
+        let machine = &mut self.machines[address];
+        if machine.halted {
+            return Ok((vec![], true));
+        }
+        let was_empty = machine.queue.is_empty();
+        match was_empty {
+            true => machine.computer.push_input(W::from_i64(-1)),
+            false => while let Some(value) = machine.queue.pop_front() {
+                machine.computer.push_input(value);
+            },
+        }
+        let (state, outputs) = machine.computer.resume().map_err(|error| MachineError { address, error })?;
+        machine.halted = state == RunState::Halted;
+        machine.outputs.extend(outputs);
+
+        let whole = machine.outputs.len() - machine.outputs.len() % 3;
+        let packets: Vec<Packet<W>> = machine.outputs.drain(..whole).collect::<Vec<W>>()
+            .chunks(3)
+            .map(|chunk| Packet {
+                source: address,
+                // An address that does not fit is as undeliverable as any other unknown one
+                destination: chunk[0].to_usize().unwrap_or(usize::MAX),
+                x: chunk[1].clone(),
+                y: chunk[2].clone(),
+            })
+            .collect();
+        let idle = was_empty && packets.is_empty();
+        Ok((packets, idle))
+    }
+
+    fn route(&mut self, packet: Packet<W>) -> PacketEvent<W> {
+        match packet.destination {
+            NAT_ADDRESS => {
+                self.nat = Some(packet.clone());
+                PacketEvent::Sent(packet)
+            },
+            destination if self.machines.get(destination).is_some_and(|machine| !machine.halted) => {
+                self.machines[destination].queue.extend(vec![packet.x.clone(), packet.y.clone()]);
+                PacketEvent::Sent(packet)
+            },
+            _ => PacketEvent::Dropped(packet),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    const SIZE: usize = 4;
+
+    // Machine 0 starts a packet round the ring. Each machine adds one to y and passes the packet on, and the
+    // last machine sends it to the NAT instead.
+    const RING: &str = "
+                INPUT [address]
+                JUMPTRUE [address], #receive
+                OUTPUT #1
+                OUTPUT #0
+                OUTPUT #100
+        receive: INPUT [x]
+                EQUAL [x], #-1, [empty]
+                JUMPTRUE [empty], #receive
+                INPUT [y]
+                ADD [address], #1, [next]
+                EQUAL [next], #4, [last]
+                JUMPFALSE [last], #forward
+                ADD #255, #0, [next]
+        forward: OUTPUT [next]
+                OUTPUT [x]
+                ADD [y], #1, [y]
+                OUTPUT [y]
+                JUMPTRUE #1, #receive
+        address: .data 0
+        x:      .data 0
+        y:      .data 0
+        empty:  .data 0
+        next:   .data 0
+        last:   .data 0
+    ";
+
+    fn ring() -> PacketNetwork {
+        PacketNetwork::new(&assemble(RING).unwrap(), SIZE)
+    }
+
+    #[test]
+    fn test_routing() {
+        let mut network = ring();
+
+        let events = network.round().unwrap();
+
+        assert_eq!(events, vec![
+            PacketEvent::Sent(Packet { source: 0, destination: 1, x: 0, y: 100 }),
+            PacketEvent::Sent(Packet { source: 1, destination: 2, x: 0, y: 101 }),
+            PacketEvent::Sent(Packet { source: 2, destination: 3, x: 0, y: 102 }),
+            PacketEvent::Sent(Packet { source: 3, destination: NAT_ADDRESS, x: 0, y: 103 }),
+        ]);
+        assert_eq!(network.nat_packet(), Some(&Packet { source: 3, destination: NAT_ADDRESS, x: 0, y: 103 }));
+    }
+
+    #[test]
+    fn test_nat_resends_when_idle() {
+        let mut network = ring();
+
+        let first = network.run_until(10, |event| matches!(event, PacketEvent::Resent(_))).unwrap();
+
+        // The ring goes quiet after the first round and the NAT waits out two idle rounds
+        assert_eq!(first, Some(PacketEvent::Resent(Packet { source: NAT_ADDRESS, destination: 0, x: 0, y: 103 })));
+        assert_eq!(network.rounds(), 3);
+        assert_eq!(network.round().unwrap(), vec![
+            PacketEvent::Sent(Packet { source: 0, destination: 1, x: 0, y: 104 }),
+            PacketEvent::Sent(Packet { source: 1, destination: 2, x: 0, y: 105 }),
+            PacketEvent::Sent(Packet { source: 2, destination: 3, x: 0, y: 106 }),
+            PacketEvent::Sent(Packet { source: 3, destination: NAT_ADDRESS, x: 0, y: 107 }),
+        ]);
+    }
+
+    #[test]
+    fn test_scheduling_is_deterministic() {
+        let record = || {
+            let mut network = ring();
+            (0..20).flat_map(|_| network.round().unwrap()).collect::<Vec<PacketEvent>>()
+        };
+
+        let events = record();
+        let resent: Vec<i64> = events.iter()
+            .filter_map(|event| match event {
+                PacketEvent::Resent(packet) => Some(packet.y),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(events, record());
+        assert_eq!(resent, vec![103,107,111,115,119,123]);
+    }
+
+    #[test]
+    fn test_dropped_packets_and_halting() {
+        let program: Vec<i64> = vec![3,100,104,7,104,1,104,2,99];
+        let mut network = PacketNetwork::new(&program, 2);
+
+        let events = network.round().unwrap();
+
+        assert_eq!(events[0], PacketEvent::Dropped(Packet { source: 0, destination: 7, x: 1, y: 2 }));
+        assert_eq!(network.round().unwrap(), vec![]);
+        assert_eq!(network.nat_packet(), None);
+    }
+
+    #[test]
+    fn test_halted_machines_take_no_packets() {
+        // Machine 0 halts straight away, and machine 1 sends it a packet and the NAT another
+        let program = assemble("
+                    INPUT [address]
+                    JUMPTRUE [address], #send
+                    HALT
+            send:   OUTPUT #0
+                    OUTPUT #1
+                    OUTPUT #2
+                    OUTPUT #255
+                    OUTPUT #3
+                    OUTPUT #4
+            wait:   INPUT [x]
+                    JUMPTRUE #1, #wait
+            address: .data 0
+            x:      .data 0
+        ").unwrap();
+        let mut network = PacketNetwork::new(&program, 2);
+
+        assert_eq!(network.round().unwrap(), vec![
+            PacketEvent::Dropped(Packet { source: 1, destination: 0, x: 1, y: 2 }),
+            PacketEvent::Sent(Packet { source: 1, destination: NAT_ADDRESS, x: 3, y: 4 }),
+        ]);
+        assert_eq!(network.run_until(10, |_| true).unwrap(), None);
+        assert_eq!(network.nat_packet(), Some(&Packet { source: 1, destination: NAT_ADDRESS, x: 3, y: 4 }));
+    }
+
+    #[test]
+    fn test_machine_errors() {
+        let mut network: PacketNetwork = PacketNetwork::new(&[3,100,42], 3);
+
+        let error = network.round().unwrap_err();
+
+        assert_eq!(error, MachineError { address: 0, error: IntcodeError::UnknownOpcode { opcode: 42, address: 2 } });
+        assert_eq!(error.to_string(), "Machine 0 failed: Opcode not recognised: 42 at address 2");
+    }
+}
@@ -0,0 +1,221 @@
+use crate::word::Word;
+use crate::{Opcode, Step};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone, Copy, Default)]
+pub struct BranchCount {
+    pub taken: usize,
+    pub not_taken: usize,
+}
+
+// A straight run of instructions that is only ever entered at its start
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone, Copy)]
+pub struct BasicBlock {
+    pub start: usize,
+    // The address just past the block's last instruction
+    pub end: usize,
+    pub instructions: usize,
+    pub executions: usize,
+}
+
+impl BasicBlock {
+    pub fn cost(&self) -> usize {
+        self.instructions * self.executions
+    }
+}
+
+#[derive(Debug)]
+#[derive(Clone, Default)]
+pub struct Profile {
+    steps: usize,
+    instructions: BTreeMap<usize, (Opcode, usize)>,
+    opcodes: BTreeMap<usize, (Opcode, usize)>,
+    reads: BTreeMap<usize, usize>,
+    writes: BTreeMap<usize, usize>,
+    branches: BTreeMap<usize, BranchCount>,
+    // Addresses control arrived at other than by falling through
+    leaders: BTreeSet<usize>,
+    next_address: Option<usize>,
+}
+
+impl Profile {
+    pub fn new() -> Profile {
+        Profile::default()
+    }
+
+    pub(crate) fn record<W: Word>(&mut self, step: &Step<W>) {
+        let size = step.opcode.parameter_count() + 1;
+        let next_address = step.jump.unwrap_or(step.address + size);
+
+        self.steps += 1;
+        self.instructions.entry(step.address).or_insert((step.opcode, 0)).1 += 1;
+        self.opcodes.entry(step.opcode.code()).or_insert((step.opcode, 0)).1 += 1;
+        for (address, _) in &step.reads {
+            *self.reads.entry(*address).or_insert(0) += 1;
+        }
+        for (address, _) in &step.writes {
+            *self.writes.entry(*address).or_insert(0) += 1;
+        }
+        if self.next_address != Some(step.address) {
+            self.leaders.insert(step.address);
+        }
+        if let Opcode::JumpTrue | Opcode::JumpFalse = step.opcode {
+            let branch = self.branches.entry(step.address).or_default();
+            match step.jump.is_some() {
+                true => branch.taken += 1,
+                false => branch.not_taken += 1,
+            }
+            self.leaders.insert(next_address);
+        }
+        self.next_address = Some(next_address);
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn executions(&self, address: usize) -> usize {
+        self.instructions.get(&address).map_or(0, |&(_, count)| count)
+    }
+
+    pub fn reads(&self, address: usize) -> usize {
+        self.reads.get(&address).copied().unwrap_or(0)
+    }
+
+    pub fn writes(&self, address: usize) -> usize {
+        self.writes.get(&address).copied().unwrap_or(0)
+    }
+
+    pub fn branch(&self, address: usize) -> Option<BranchCount> {
+        self.branches.get(&address).copied()
+    }
+
+    // Most executed first
+    pub fn opcode_counts(&self) -> Vec<(Opcode, usize)> {
+        let mut counts: Vec<(Opcode, usize)> = self.opcodes.values().copied().collect();
+        counts.sort_by(|(_, left), (_, right)| right.cmp(left));
+        counts
+    }
+
+    // Costliest first, by the number of instructions executed inside each block
+    pub fn hot_blocks(&self) -> Vec<BasicBlock> {
+        let mut blocks = vec![];
+        for &start in &self.leaders {
+            let executions = self.executions(start);
+            if executions == 0 {
+                continue;
+            }
+            let mut block = BasicBlock { start, end: start, instructions: 0, executions };
+            while let Some(&(opcode, _)) = self.instructions.get(&block.end) {
+                if block.instructions > 0 && self.leaders.contains(&block.end) {
+                    break;
+                }
+                block.instructions += 1;
+                block.end += opcode.parameter_count() + 1;
+                if let Opcode::JumpTrue | Opcode::JumpFalse = opcode {
+                    break;
+                }
+            }
+            blocks.push(block);
+        }
+        blocks.sort_by(|left, right| right.cost().cmp(&left.cost()).then(left.start.cmp(&right.start)));
+        blocks
+    }
+
+    pub fn report(&self, block_limit: usize) -> String {
+        let percent = |count: usize| match self.steps {
+            0 => 0.0,
+            steps => count as f64 * 100.0 / steps as f64,
+        };
+        let mut report = format!("instructions executed: {}\n", self.steps);
+
+        report.push_str("opcodes:\n");
+        for (opcode, count) in self.opcode_counts() {
+            let _ = writeln!(report, "  {:<12} {:>10} {:>6.2}%", opcode.mnemonic(), count, percent(count));
+        }
+        report.push_str("hot blocks:\n");
+        for block in self.hot_blocks().iter().take(block_limit) {
+            let _ = write!(report, "  {:04}..{:04} {:>10} x {:<3} {:>6.2}%", block.start, block.end, block.executions, block.instructions, percent(block.cost()));
+            let last_address = self.instructions.range(..block.end).next_back().map(|(&address, _)| address);
+            if let Some(branch) = last_address.and_then(|address| self.branch(address)) {
+                let _ = write!(report, "  taken {} not taken {}", branch.taken, branch.not_taken);
+            }
+            report.push('\n');
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_program, IntcodeComputer};
+    use super::*;
+    use std::fs;
+
+    fn profile(program: &[i64], inputs: Vec<i64>) -> Profile {
+        let mut computer = IntcodeComputer::new();
+        computer.set_profiling(true);
+        computer.load(program);
+        computer.run(Some(inputs)).unwrap();
+        computer.profile().unwrap().clone()
+    }
+
+    #[test]
+    fn test_counts() {
+        // Counts mem[13] down from the input to zero, outputting each value
+        let program: Vec<i64> = vec![3,13,4,13,1001,13,-1,13,1005,13,2,99,0,0];
+
+        let profile = profile(&program, vec![3]);
+
+        assert_eq!(profile.steps(), 10);
+        assert_eq!(profile.executions(0), 1);
+        assert_eq!(profile.executions(2), 3);
+        assert_eq!(profile.reads(13), 9);
+        assert_eq!(profile.writes(13), 4);
+        assert_eq!(profile.branch(8), Some(BranchCount { taken: 2, not_taken: 1 }));
+        assert_eq!(profile.opcode_counts(), vec![(Opcode::Add, 3), (Opcode::Output, 3), (Opcode::JumpTrue, 3), (Opcode::Input, 1)]);
+    }
+
+    #[test]
+    fn test_hot_blocks() {
+        let program: Vec<i64> = vec![3,13,4,13,1001,13,-1,13,1005,13,2,99,0,0];
+
+        let blocks = profile(&program, vec![3]).hot_blocks();
+
+        assert_eq!(blocks, vec![
+            BasicBlock { start: 2, end: 11, instructions: 3, executions: 3 },
+            BasicBlock { start: 0, end: 2, instructions: 1, executions: 1 },
+        ]);
+    }
+
+    #[test]
+    fn test_report() {
+        let program: Vec<i64> = vec![3,13,4,13,1001,13,-1,13,1005,13,2,99,0,0];
+
+        let report = profile(&program, vec![3]).report(1);
+
+        assert!(report.starts_with("instructions executed: 10\nopcodes:\n"));
+        assert!(report.contains("  INPUT                 1  10.00%\n"));
+        assert!(report.contains("  0002..0011          3 x 3    90.00%  taken 2 not taken 1\n"));
+        assert!(!report.contains("0000..0002"));
+    }
+
+    #[test]
+    fn test_day_9_part_2_profile() {
+        let program = parse_program(&fs::read_to_string("input_day_9.txt").unwrap()).unwrap();
+
+        let profile = profile(&program, vec![2]);
+        let blocks = profile.hot_blocks();
+
+        assert_eq!(profile.opcode_counts().iter().map(|(_, count)| count).sum::<usize>(), profile.steps());
+        assert_eq!(blocks.iter().map(|block| block.cost()).sum::<usize>(), profile.steps());
+        assert!(blocks.windows(2).all(|pair| pair[0].cost() >= pair[1].cost()));
+        assert!(profile.steps() > 100_000);
+    }
+}
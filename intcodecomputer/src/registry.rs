@@ -0,0 +1,321 @@
+use crate::instruction::opcode::CustomOpcode;
+use crate::memory::{Memory, PagedMemory};
+use crate::word::Word;
+use crate::{Arithmetic, Instruction, IntcodeError, Opcode, ParameterMode, MAX_PARAMETERS};
+
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+const BUILT_IN: [Opcode; 10] = [
+    Opcode::Add,
+    Opcode::Multiply,
+    Opcode::Input,
+    Opcode::Output,
+    Opcode::JumpTrue,
+    Opcode::JumpFalse,
+    Opcode::Less,
+    Opcode::Equal,
+    Opcode::RelativeBase,
+    Opcode::Halt,
+];
+
+type Execute<W> = Arc<dyn Fn(&mut Context<W>) -> Result<(), IntcodeError<W>> + Send + Sync>;
+type UnknownHandler<W> = Arc<dyn Fn(&W, usize) -> UnknownOpcodeAction + Send + Sync>;
+
+// What the computer does on reaching a code no opcode is registered for
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone, Copy)]
+pub enum UnknownOpcodeAction {
+    Fail,
+    Halt,
+    // Steps over the code as if it were a one word instruction that does nothing
+    Skip,
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum RegistrationError {
+    InvalidCode(usize),
+    BuiltIn(usize),
+    TooManyParameters { code: usize, parameter_count: usize },
+    InvalidWriteParameter { code: usize, write_parameter: usize },
+}
+
+impl fmt::Display for RegistrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistrationError::InvalidCode(code) => write!(f, "Opcode {} is not a two digit code", code),
+            RegistrationError::BuiltIn(code) => write!(f, "Opcode {} is built in and cannot be replaced", code),
+            RegistrationError::TooManyParameters { code, parameter_count } => write!(f, "Opcode {} has {} parameters, at most {} are supported", code, parameter_count, MAX_PARAMETERS),
+            RegistrationError::InvalidWriteParameter { code, write_parameter } => write!(f, "Opcode {} writes to parameter {}, which it does not have", code, write_parameter),
+        }
+    }
+}
+
+impl Error for RegistrationError {}
+
+// The opcodes a computer decodes, indexed by their two digit code. The built-in ten are registered from the start.
+// They run through the computer itself rather than a closure, so they can be removed but not replaced.
+pub struct OpcodeRegistry<W: Word = i64> {
+    opcodes: [Option<Opcode>; 100],
+    execute: Vec<(usize, Execute<W>)>,
+    unknown: Option<UnknownHandler<W>>,
+}
+
+impl<W: Word> Clone for OpcodeRegistry<W> {
+    fn clone(&self) -> Self {
+        OpcodeRegistry {
+            opcodes: self.opcodes,
+            execute: self.execute.clone(),
+            unknown: self.unknown.clone(),
+        }
+    }
+}
+
+impl<W: Word> fmt::Debug for OpcodeRegistry<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.opcodes.iter().flatten()).finish()
+    }
+}
+
+impl<W: Word> Default for OpcodeRegistry<W> {
+    fn default() -> Self {
+        let mut registry = OpcodeRegistry::empty();
+        for opcode in BUILT_IN.iter() {
+            registry.opcodes[opcode.code()] = Some(*opcode);
+        }
+        registry
+    }
+}
+
+impl<W: Word> OpcodeRegistry<W> {
+    pub fn new() -> OpcodeRegistry<W> {
+        OpcodeRegistry::default()
+    }
+
+    pub fn empty() -> OpcodeRegistry<W> {
+        OpcodeRegistry {
+            opcodes: [None; 100],
+            execute: vec![],
+            unknown: None,
+        }
+    }
+
+    // Adds an instruction, replacing whatever custom instruction was registered under its code
+    pub fn register<F>(&mut self, opcode: CustomOpcode, execute: F) -> Result<(), RegistrationError>
+    where
+        F: Fn(&mut Context<W>) -> Result<(), IntcodeError<W>> + Send + Sync + 'static,
+    {
+        if opcode.code >= 100 {
+            return Err(RegistrationError::InvalidCode(opcode.code));
+        }
+        if Opcode::from(opcode.code).is_some() {
+            return Err(RegistrationError::BuiltIn(opcode.code));
+        }
+        if opcode.parameter_count > MAX_PARAMETERS {
+            return Err(RegistrationError::TooManyParameters { code: opcode.code, parameter_count: opcode.parameter_count });
+        }
+        if let Some(&write_parameter) = opcode.write_parameters.iter().find(|&&index| index >= opcode.parameter_count) {
+            return Err(RegistrationError::InvalidWriteParameter { code: opcode.code, write_parameter });
+        }
+        self.remove(opcode.code);
+        self.opcodes[opcode.code] = Some(Opcode::Custom(opcode));
+        self.execute.push((opcode.code, Arc::new(execute)));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, code: usize) -> Option<Opcode> {
+        self.execute.retain(|(registered, _)| *registered != code);
+        self.opcodes.get_mut(code)?.take()
+    }
+
+    pub fn get(&self, code: usize) -> Option<Opcode> {
+        self.opcodes.get(code).copied().flatten()
+    }
+
+    pub fn opcodes(&self) -> impl Iterator<Item = Opcode> + '_ {
+        self.opcodes.iter().flatten().copied()
+    }
+
+    // Without a handler unknown opcodes fail the run with IntcodeError::UnknownOpcode
+    pub fn set_unknown_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&W, usize) -> UnknownOpcodeAction + Send + Sync + 'static,
+    {
+        self.unknown = Some(Arc::new(handler));
+    }
+
+    pub fn clear_unknown_handler(&mut self) {
+        self.unknown = None;
+    }
+
+    pub fn decode(&self, raw_code: &W, address: usize) -> Result<(Opcode, [ParameterMode; MAX_PARAMETERS]), IntcodeError<W>> {
+        Instruction::decode_with(raw_code, address, |code| self.get(code))
+    }
+
+    // The opcode a step was recorded under. A code nothing is registered for can only have been recorded by an
+    // unknown opcode handler skipping it.
+    pub(crate) fn recorded(&self, code: usize) -> Option<Opcode> {
+        match (self.get(code), &self.unknown) {
+            (Some(opcode), _) => Some(opcode),
+            (None, Some(_)) if code < 100 => Some(skip(code)),
+            (None, _) => None,
+        }
+    }
+
+    pub(crate) fn unknown(&self, raw_code: &W, address: usize) -> UnknownOpcodeAction {
+        match &self.unknown {
+            Some(handler) => handler(raw_code, address),
+            None => UnknownOpcodeAction::Fail,
+        }
+    }
+
+    pub(crate) fn executor(&self, opcode: CustomOpcode) -> Option<&Execute<W>> {
+        match self.get(opcode.code) == Some(Opcode::Custom(opcode)) {
+            true => self.execute.iter().find(|(code, _)| *code == opcode.code).map(|(_, execute)| execute),
+            false => None,
+        }
+    }
+}
+
+// Stands in for an unknown code the unknown opcode handler chose to skip
+pub(crate) fn skip(code: usize) -> Opcode {
+    Opcode::Custom(CustomOpcode { code, mnemonic: "SKIP", parameter_count: 0, write_parameters: &[] })
+}
+
+// A custom instruction's view of the machine while it executes
+pub struct Context<'a, W: Word> {
+    memory: &'a mut PagedMemory<W>,
+    instruction: &'a Instruction,
+    address: usize,
+    pub(crate) relative_base: usize,
+    pub(crate) arithmetic: Arithmetic,
+    pub(crate) output: Option<W>,
+    pub(crate) jump: Option<usize>,
+    pub(crate) halt: bool,
+}
+
+impl<'a, W: Word> Context<'a, W> {
+    pub(crate) fn new(memory: &'a mut PagedMemory<W>, instruction: &'a Instruction, address: usize, relative_base: usize, arithmetic: Arithmetic) -> Context<'a, W> {
+        Context {
+            memory,
+            instruction,
+            address,
+            relative_base,
+            arithmetic,
+            output: None,
+            jump: None,
+            halt: false,
+        }
+    }
+
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    // The value of a parameter, resolved through its mode
+    pub fn parameter(&self, index: usize) -> W {
+        self.memory.read(self.instruction.parameter_positions()[index])
+    }
+
+    pub fn read(&self, address: usize) -> W {
+        self.memory.read(address)
+    }
+
+    // Writes to the opcode's first write parameter
+    pub fn write(&mut self, value: W) -> Result<(), IntcodeError<W>> {
+        match self.instruction.opcode.write_parameters().first() {
+            Some(&index) => self.write_to(index, value),
+            None => Err(self.no_write_parameter()),
+        }
+    }
+
+    // Writes to the parameter at the index, which must be one of the opcode's write parameters
+    pub fn write_to(&mut self, index: usize, value: W) -> Result<(), IntcodeError<W>> {
+        match self.instruction.opcode.write_parameters().contains(&index) {
+            true => self.memory.write(self.instruction.parameter_positions()[index], value),
+            false => Err(self.no_write_parameter()),
+        }
+    }
+
+    fn no_write_parameter(&self) -> IntcodeError<W> {
+        IntcodeError::NoWriteParameter { opcode: W::from_usize(self.instruction.opcode.code()), address: self.address }
+    }
+
+    pub fn relative_base(&self) -> usize {
+        self.relative_base
+    }
+
+    pub fn set_relative_base(&mut self, relative_base: usize) {
+        self.relative_base = relative_base;
+    }
+
+    pub fn arithmetic(&self) -> Arithmetic {
+        self.arithmetic
+    }
+
+    pub fn output(&mut self, value: W) {
+        self.output = Some(value);
+    }
+
+    // Continues at the target instead of the next instruction
+    pub fn jump(&mut self, target: usize) {
+        self.jump = Some(target);
+    }
+
+    // Stops the computer at this instruction. Running it again reports it as halted without executing anything.
+    pub fn halt(&mut self) {
+        self.halt = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWAP: CustomOpcode = CustomOpcode { code: 42, mnemonic: "SWAP", parameter_count: 2, write_parameters: &[0, 1] };
+
+    #[test]
+    fn test_built_in_set() {
+        let registry: OpcodeRegistry = OpcodeRegistry::new();
+
+        assert_eq!(registry.opcodes().collect::<Vec<Opcode>>(), BUILT_IN.to_vec());
+        assert_eq!(registry.decode(&1002, 0), Ok((Opcode::Multiply, [ParameterMode::PositionalMode, ParameterMode::ImmediateMode, ParameterMode::PositionalMode])));
+        assert_eq!(OpcodeRegistry::<i64>::empty().decode(&99, 0), Err(IntcodeError::UnknownOpcode { opcode: 99, address: 0 }));
+    }
+
+    #[test]
+    fn test_register_and_remove() {
+        let mut registry: OpcodeRegistry = OpcodeRegistry::new();
+
+        registry.register(SWAP, |_| Ok(())).unwrap();
+
+        assert_eq!(registry.decode(&42, 3).map(|(opcode, _)| opcode), Ok(Opcode::Custom(SWAP)));
+        assert!(registry.executor(SWAP).is_some());
+        assert_eq!(registry.remove(42), Some(Opcode::Custom(SWAP)));
+        assert!(registry.executor(SWAP).is_none());
+        assert_eq!(registry.remove(42), None);
+    }
+
+    #[test]
+    fn test_invalid_registrations() {
+        let mut registry: OpcodeRegistry = OpcodeRegistry::new();
+
+        assert_eq!(registry.register(CustomOpcode { code: 100, ..SWAP }, |_| Ok(())), Err(RegistrationError::InvalidCode(100)));
+        assert_eq!(registry.register(CustomOpcode { parameter_count: 4, ..SWAP }, |_| Ok(())), Err(RegistrationError::TooManyParameters { code: 42, parameter_count: 4 }));
+        assert_eq!(registry.register(CustomOpcode { write_parameters: &[0, 2], ..SWAP }, |_| Ok(())), Err(RegistrationError::InvalidWriteParameter { code: 42, write_parameter: 2 }));
+        assert_eq!(registry.register(CustomOpcode { code: 7, ..SWAP }, |_| Ok(())), Err(RegistrationError::BuiltIn(7)));
+        assert_eq!(registry.register(CustomOpcode { code: 99, ..SWAP }, |_| Ok(())), Err(RegistrationError::BuiltIn(99)));
+        assert_eq!(registry.decode(&42, 0), Err(IntcodeError::UnknownOpcode { opcode: 42, address: 0 }));
+    }
+
+    #[test]
+    fn test_write_parameter_cannot_be_immediate() {
+        let mut registry: OpcodeRegistry = OpcodeRegistry::new();
+        registry.register(SWAP, |_| Ok(())).unwrap();
+
+        assert_eq!(registry.decode(&1042, 0), Err(IntcodeError::WriteToImmediate { address: 0 }));
+    }
+}
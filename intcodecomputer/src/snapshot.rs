@@ -0,0 +1,276 @@
+use crate::memory::{Memory, PagedMemory};
+use crate::word::Word;
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+pub const SNAPSHOT_VERSION: u32 = 2;
+const SNAPSHOT_HEADER: &str = "intcode-snapshot";
+
+// Everything needed to carry on running a computer exactly where it left off
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub struct Snapshot<W: Word = i64> {
+    pub memory: PagedMemory<W>,
+    pub position: usize,
+    pub relative_base: usize,
+    pub inputs: Vec<W>,
+    // Where a custom instruction halted the computer
+    pub halted_at: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    UnsupportedVersion(String),
+    Malformed { line: usize, message: String },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "Could not access snapshot: {}", error),
+            SnapshotError::UnsupportedVersion(version) => write!(f, "Snapshot version {} is not supported", version),
+            SnapshotError::Malformed { line, message } => write!(f, "Malformed snapshot at line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> SnapshotError {
+        SnapshotError::Io(error)
+    }
+}
+
+// The on-disk format is line based text:
+//   intcode-snapshot 2
+//   position 12
+//   relative_base 2000
+//   inputs 5,7
+//   image 1,2,3
+//   sparse 2003:8
+//   halted_at 12
+// with halted_at left empty unless the computer was halted there. Version 1 had no halted_at line.
+impl<W: Word> Snapshot<W> {
+    pub fn write_to<T: Write>(&self, mut writer: T) -> io::Result<()> {
+        let sparse: Vec<String> = self.memory.sparse_cells().iter()
+            .map(|(address, value)| format!("{}:{}", address, value))
+            .collect();
+
+        writeln!(writer, "{} {}", SNAPSHOT_HEADER, SNAPSHOT_VERSION)?;
+        writeln!(writer, "position {}", self.position)?;
+        writeln!(writer, "relative_base {}", self.relative_base)?;
+        writeln!(writer, "inputs {}", join(&self.inputs))?;
+        writeln!(writer, "image {}", join(self.memory.image()))?;
+        writeln!(writer, "sparse {}", sparse.join(","))?;
+        match self.halted_at {
+            Some(address) => writeln!(writer, "halted_at {}", address)?,
+            None => writeln!(writer, "halted_at")?,
+        }
+        writer.flush()
+    }
+
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Snapshot<W>, SnapshotError> {
+        let mut lines = reader.lines();
+        let mut next_field = |line: usize, name: &str| -> Result<String, SnapshotError> {
+            let text = lines.next().ok_or_else(|| malformed(line, &format!("missing {}", name)))??;
+            match text.split_once(' ') {
+                Some((field, value)) if field == name => Ok(value.trim().to_string()),
+                None if text == name => Ok(String::new()),
+                _ => Err(malformed(line, &format!("expected {}", name))),
+            }
+        };
+
+        let version = next_field(1, SNAPSHOT_HEADER)?;
+        if version != "1" && version != SNAPSHOT_VERSION.to_string() {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let position = parse(2, &next_field(2, "position")?)?;
+        let relative_base = parse(3, &next_field(3, "relative_base")?)?;
+        let inputs = parse_words(4, &next_field(4, "inputs")?)?;
+        let mut memory = PagedMemory::from(parse_words(5, &next_field(5, "image")?)?.as_slice());
+        for cell in next_field(6, "sparse")?.split(',').filter(|cell| !cell.is_empty()) {
+            let (address, value) = cell.split_once(':').ok_or_else(|| malformed(6, cell))?;
+            memory.write(parse(6, address)?, parse_word(6, value)?).map_err(|error| malformed(6, &error.to_string()))?;
+        }
+
+        let halted_at = match version.as_str() {
+            "1" => None,
+            _ => match next_field(7, "halted_at")?.as_str() {
+                "" => None,
+                address => Some(parse(7, address)?),
+            },
+        };
+
+        Ok(Snapshot { memory, position, relative_base, inputs, halted_at })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Snapshot<W>, SnapshotError> {
+        Snapshot::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+fn join<W: Word>(values: &[W]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(",")
+}
+
+fn malformed(line: usize, message: &str) -> SnapshotError {
+    SnapshotError::Malformed { line, message: message.to_string() }
+}
+
+fn parse<T: FromStr>(line: usize, text: &str) -> Result<T, SnapshotError> {
+    text.trim().parse::<T>().map_err(|_| malformed(line, &format!("invalid number {}", text)))
+}
+
+fn parse_word<W: Word>(line: usize, text: &str) -> Result<W, SnapshotError> {
+    W::parse_word(text).map_err(|_| malformed(line, &format!("invalid number {}", text)))
+}
+
+fn parse_words<W: Word>(line: usize, text: &str) -> Result<Vec<W>, SnapshotError> {
+    text.split(',').filter(|value| !value.is_empty()).map(|value| parse_word(line, value)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_program, BigInt, CustomOpcode, IntcodeComputer, OpcodeRegistry, RunState, StepOutcome};
+    use std::fs;
+
+    #[test]
+    fn test_text_round_trip() {
+        let mut memory: PagedMemory = PagedMemory::from(&[1,-2,3][..]);
+        memory.write(5000, -9).unwrap();
+        let snapshot = Snapshot { memory, position: 2, relative_base: 4000, inputs: vec![7,8], halted_at: None };
+        let mut bytes: Vec<u8> = Vec::new();
+
+        snapshot.write_to(&mut bytes).unwrap();
+
+        assert_eq!(String::from_utf8(bytes.clone()).unwrap(), "\
+intcode-snapshot 2
+position 2
+relative_base 4000
+inputs 7,8
+image 1,-2,3
+sparse 5000:-9
+halted_at
+");
+        assert_eq!(Snapshot::read_from(bytes.as_slice()).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_version_1() {
+        let text = "intcode-snapshot 1\nposition 1\nrelative_base 0\ninputs\nimage 104,5,99\nsparse\n";
+
+        let snapshot = Snapshot::<i64>::read_from(text.as_bytes()).unwrap();
+
+        assert_eq!(snapshot.position, 1);
+        assert_eq!(snapshot.halted_at, None);
+    }
+
+    #[test]
+    fn test_restore_keeps_custom_halt() {
+        let mut opcodes = OpcodeRegistry::new();
+        opcodes.register(CustomOpcode { code: 50, mnemonic: "STOP", parameter_count: 0, write_parameters: &[] }, |context| {
+            context.halt();
+            Ok(())
+        }).unwrap();
+        let mut computer = IntcodeComputer::new();
+        computer.set_opcodes(opcodes.clone());
+        computer.load(&[104,1,50,104,2,99]);
+        assert_eq!(computer.resume().unwrap(), (RunState::Halted, vec![1]));
+        let mut bytes: Vec<u8> = Vec::new();
+
+        computer.snapshot().write_to(&mut bytes).unwrap();
+        let snapshot = Snapshot::read_from(bytes.as_slice()).unwrap();
+        let mut restored = IntcodeComputer::new();
+        restored.set_opcodes(opcodes);
+        restored.restore(&snapshot);
+
+        assert_eq!(snapshot.halted_at, Some(2));
+        assert!(String::from_utf8(bytes).unwrap().ends_with("halted_at 2\n"));
+        assert_eq!(restored.resume().unwrap(), (RunState::Halted, vec![]));
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let text = "intcode-snapshot 9\nposition 0\n";
+
+        match Snapshot::<i64>::read_from(text.as_bytes()) {
+            Err(SnapshotError::UnsupportedVersion(version)) => assert_eq!(version, "9"),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_malformed() {
+        let text = "intcode-snapshot 1\nposition x\n";
+
+        match Snapshot::<i64>::read_from(text.as_bytes()) {
+            Err(SnapshotError::Malformed { line, .. }) => assert_eq!(line, 2),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_restore_reproduces_day_9_outputs() {
+        let program = parse_program(&fs::read_to_string("input_day_9.txt").unwrap()).unwrap();
+        let mut computer = IntcodeComputer::new();
+        computer.load(&program);
+        computer.push_input(2);
+        let expected_outputs = computer.run(None).unwrap();
+
+        computer.load(&program);
+        computer.push_input(2);
+        for _ in 0..50000 {
+            assert!(matches!(computer.step().unwrap(), StepOutcome::Executed(_)));
+        }
+        let mut bytes: Vec<u8> = Vec::new();
+        computer.snapshot().write_to(&mut bytes).unwrap();
+
+        let mut restored = IntcodeComputer::new();
+        restored.restore(&Snapshot::read_from(bytes.as_slice()).unwrap());
+
+        assert_eq!(restored.position(), computer.position());
+        assert_eq!(restored.relative_base(), computer.relative_base());
+        assert_eq!(restored.resume().unwrap(), (RunState::Halted, expected_outputs.clone()));
+        assert_eq!(computer.resume().unwrap(), (RunState::Halted, expected_outputs));
+    }
+
+    #[test]
+    fn test_big_words() {
+        let mut memory = PagedMemory::from(&[BigInt::from(i128::MAX)][..]);
+        memory.write(5000, BigInt::from(-9i64)).unwrap();
+        let snapshot = Snapshot { memory, position: 0, relative_base: 0, inputs: vec![BigInt::from(i128::MIN)], halted_at: None };
+        let mut bytes: Vec<u8> = Vec::new();
+
+        snapshot.write_to(&mut bytes).unwrap();
+
+        assert_eq!(Snapshot::read_from(bytes.as_slice()).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_restore_keeps_pending_inputs() {
+        let mut computer = IntcodeComputer::new();
+        computer.load(&[3,0,4,0,3,0,4,0,99]);
+        computer.push_input(4);
+        computer.push_input(5);
+        let snapshot = computer.snapshot();
+
+        assert_eq!(computer.run(None).unwrap(), vec![4,5]);
+
+        computer.restore(&snapshot);
+
+        assert_eq!(computer.run(None).unwrap(), vec![4,5]);
+    }
+}
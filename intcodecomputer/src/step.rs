@@ -0,0 +1,27 @@
+use crate::instruction::opcode::Opcode;
+use crate::instruction::parametermode::ParameterMode;
+use crate::word::Word;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub struct Step<W: Word = i64> {
+    pub address: usize,
+    pub opcode: Opcode,
+    pub parameter_modes: Vec<ParameterMode>,
+    pub parameter_positions: Vec<usize>,
+    pub reads: Vec<(usize, W)>,
+    pub writes: Vec<(usize, W)>,
+    pub jump: Option<usize>,
+    pub input: Option<W>,
+    pub output: Option<W>,
+    pub relative_base: usize,
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum StepOutcome<W: Word = i64> {
+    Executed(Step<W>),
+    Halted,
+    AwaitingInput,
+}
@@ -0,0 +1,256 @@
+use crate::ascii::{AsciiComputer, AsciiEvent};
+use crate::word::Word;
+use crate::{IntcodeComputer, Snapshot};
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+// Plays a program that talks in ASCII. Each line typed is sent to the program once it asks for input, unless it
+// starts with a colon, which makes it a command to the terminal. A line that really starts with a colon is sent
+// by doubling it.
+pub struct Terminal<W: Word = i64> {
+    computer: AsciiComputer<W>,
+    history: Vec<String>,
+    // How long the history was when each snapshot was saved, so loading one can wind the history back with it
+    saved: HashMap<String, usize>,
+    halted: bool,
+}
+
+impl<W: Word> Terminal<W> {
+    pub fn new(computer: IntcodeComputer<W>) -> Terminal<W> {
+        Terminal {
+            computer: AsciiComputer::new(computer),
+            history: vec![],
+            saved: HashMap::new(),
+            halted: false,
+        }
+    }
+
+    pub fn computer(&self) -> &IntcodeComputer<W> {
+        self.computer.computer()
+    }
+
+    // True once the program has halted or stopped with an error, until a snapshot is loaded
+    pub fn halted(&self) -> bool {
+        self.halted
+    }
+
+    // The lines that led to the program's current state, which replayed as a script reproduces it. Lines starting
+    // with a colon are kept escaped, and a snapshot this session did not save is loaded by the script's first line.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    pub fn run<R: BufRead, T: Write>(&mut self, input: R, mut output: T) -> io::Result<()> {
+        self.show(&mut output)?;
+        for line in input.lines() {
+            if !self.execute(&line?, &mut output)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Like run, but echoes each line so the transcript reads as if it had been typed. Returns false if the
+    // script quit.
+    pub fn replay<R: BufRead, T: Write>(&mut self, script: R, output: &mut T) -> io::Result<bool> {
+        self.show(output)?;
+        for line in script.lines() {
+            let line = line?;
+            writeln!(output, "> {}", line)?;
+            if !self.execute(&line, output)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    // Returns false once the session should end
+    pub fn execute<T: Write>(&mut self, line: &str, output: &mut T) -> io::Result<bool> {
+        let command = match line.strip_prefix(':') {
+            Some(command) if !command.starts_with(':') => command.trim(),
+            Some(escaped) => return self.send(escaped, output),
+            None => return self.send(line, output),
+        };
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, Some(argument.trim())),
+            None => (command, None),
+        };
+
+        match (name, argument) {
+            ("save", Some(path)) => match self.computer().snapshot().save(path) {
+                Ok(()) => {
+                    self.saved.insert(path.to_string(), self.history.len());
+                    writeln!(output, "[saved to {}]", path)?;
+                },
+                Err(error) => writeln!(output, "[could not save {}: {}]", path, error)?,
+            },
+            ("save", None) => writeln!(output, "usage: :save <path>")?,
+            ("load", Some(path)) => match Snapshot::load(path) {
+                Ok(snapshot) => {
+                    self.computer.restore(&snapshot);
+                    self.halted = false;
+                    match self.saved.get(path) {
+                        Some(&length) => self.history.truncate(length),
+                        None => self.history = vec![format!(":load {}", path)],
+                    }
+                    writeln!(output, "[loaded {}]", path)?;
+                    self.show(output)?;
+                },
+                Err(error) => writeln!(output, "[could not load {}: {}]", path, error)?,
+            },
+            ("load", None) => writeln!(output, "usage: :load <path>")?,
+            ("history", None) => for line in &self.history {
+                writeln!(output, "{}", line)?;
+            },
+            ("history", Some(path)) => {
+                let script: String = self.history.iter().map(|line| format!("{}\n", line)).collect();
+                match fs::write(path, script) {
+                    Ok(()) => writeln!(output, "[history written to {}]", path)?,
+                    Err(error) => writeln!(output, "[could not write {}: {}]", path, error)?,
+                }
+            },
+            ("help", _) => {
+                writeln!(output, ":save <path>, :load <path>, :history [path], :quit")?;
+                writeln!(output, "anything else is sent to the program, with a leading :: sending a single :")?;
+            },
+            ("quit", _) => return Ok(false),
+            _ => writeln!(output, "[unknown command: {}]", name)?,
+        }
+        Ok(true)
+    }
+
+    fn send<T: Write>(&mut self, line: &str, output: &mut T) -> io::Result<bool> {
+        if self.halted {
+            writeln!(output, "[the program has halted, :load a saved game or :quit]")?;
+            return Ok(true);
+        }
+        self.history.push(match line.starts_with(':') {
+            true => format!(":{}", line),
+            false => line.to_string(),
+        });
+        self.computer.send_line(line);
+        self.show(output)?;
+        Ok(true)
+    }
+
+    // Prints whatever the program has to say before it next needs input
+    fn show<T: Write>(&mut self, output: &mut T) -> io::Result<()> {
+        loop {
+            match self.computer.next_event() {
+                Ok(AsciiEvent::Line(line)) => writeln!(output, "{}", line)?,
+                Ok(AsciiEvent::Value(value)) => writeln!(output, "{}", value)?,
+                Ok(AsciiEvent::AwaitingInput) => break,
+                Ok(AsciiEvent::Halted) => {
+                    self.halted = true;
+                    writeln!(output, "[halted]")?;
+                    break;
+                },
+                Err(error) => {
+                    self.halted = true;
+                    writeln!(output, "[error: {}]", error)?;
+                    break;
+                },
+            }
+        }
+        output.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii::tests::greeter;
+    use std::env;
+
+    fn terminal() -> Terminal {
+        let mut computer = IntcodeComputer::new();
+        computer.load(&greeter());
+        Terminal::new(computer)
+    }
+
+    fn session(script: &str) -> (String, Vec<String>) {
+        let mut terminal = terminal();
+        let mut output: Vec<u8> = Vec::new();
+
+        terminal.run(script.as_bytes(), &mut output).unwrap();
+
+        (String::from_utf8(output).unwrap(), terminal.history().to_vec())
+    }
+
+    fn replay(script: &str) -> (bool, String) {
+        let mut output: Vec<u8> = Vec::new();
+        let finished = terminal().replay(script.as_bytes(), &mut output).unwrap();
+        (finished, String::from_utf8(output).unwrap())
+    }
+
+    fn terminal_halted(script: &str) -> bool {
+        let mut terminal = terminal();
+        terminal.replay(script.as_bytes(), &mut Vec::new()).unwrap();
+        terminal.halted()
+    }
+
+    #[test]
+    fn test_conversation() {
+        let (output, history) = session("Ada\nagain\n:history\n");
+
+        assert!(terminal_halted("Ada\n"));
+        assert!(!terminal_halted(""));
+        assert_eq!(output, "Name? \nAdaHi!\n1000\n[halted]\n[the program has halted, :load a saved game or :quit]\nAda\n");
+        assert_eq!(history, vec![String::from("Ada")]);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = env::temp_dir().join(format!("terminal_save_{}.snapshot", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let (output, history) = session(&format!(":save {0}\nAda\n:load {0}\nBo\n:quit\nCy\n", path));
+        let _ = fs::remove_file(path);
+
+        assert_eq!(output, format!("Name? \n[saved to {0}]\nAdaHi!\n1000\n[halted]\n[loaded {0}]\nBoHi!\n1000\n[halted]\n", path));
+        assert_eq!(history, vec![String::from("Bo")]);
+    }
+
+    #[test]
+    fn test_commands() {
+        let (output, history) = session(":frobnicate\n:load\n:load /nonexistent/save\n::)\n");
+
+        assert!(output.contains("[unknown command: frobnicate]\n"));
+        assert!(output.contains("usage: :load <path>\n"));
+        assert!(output.contains("[could not load /nonexistent/save: "));
+        assert!(output.ends_with(":)Hi!\n1000\n[halted]\n"));
+        assert_eq!(history, vec![String::from("::)")]);
+    }
+
+    #[test]
+    fn test_history_replays_the_session() {
+        let path = env::temp_dir().join(format!("terminal_history_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        let (first, _) = session(&format!("Ada\n:history {}\n", path));
+        let script = fs::read_to_string(path).unwrap();
+        let _ = fs::remove_file(path);
+
+
+        assert_eq!(script, "Ada\n");
+        assert!(first.starts_with("Name? \nAdaHi!\n1000\n[halted]\n"));
+        assert_eq!(replay(&script), (true, String::from("Name? \n> Ada\nAdaHi!\n1000\n[halted]\n")));
+    }
+
+    #[test]
+    fn test_history_after_load_replays_the_session() {
+        let snapshot = env::temp_dir().join(format!("terminal_replay_{}.snapshot", std::process::id()));
+        let snapshot = snapshot.to_str().unwrap();
+
+        let (_, within_session) = session(&format!(":save {0}\nAda\n:load {0}\n::)\n", snapshot));
+        let (_, across_sessions) = session(&format!(":load {}\nBo\n", snapshot));
+        let replayed = replay(&across_sessions.iter().map(|line| format!("{}\n", line)).collect::<String>());
+        let _ = fs::remove_file(snapshot);
+
+        assert_eq!(within_session, vec![String::from("::)")]);
+        assert_eq!(replay("::)\n"), (true, String::from("Name? \n> ::)\n:)Hi!\n1000\n[halted]\n")));
+        assert_eq!(across_sessions, vec![format!(":load {}", snapshot), String::from("Bo")]);
+        assert_eq!(replayed, (true, format!("Name? \n> :load {0}\n[loaded {0}]\n> Bo\nBoHi!\n1000\n[halted]\n", snapshot)));
+    }
+}
@@ -0,0 +1,417 @@
+use crate::registry::OpcodeRegistry;
+use crate::word::Word;
+use crate::{ParameterMode, Step};
+
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Read, Write};
+
+pub const BINARY_MAGIC: &[u8; 4] = b"ICTR";
+pub const BINARY_VERSION: u8 = 3;
+
+const HAS_JUMP: u8 = 2;
+const HAS_INPUT: u8 = 4;
+const HAS_OUTPUT: u8 = 8;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone, Copy)]
+pub enum TraceFormat {
+    JsonLines,
+    Binary,
+}
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub struct TraceRecord<W: Word = i64> {
+    pub step_number: usize,
+    pub step: Step<W>,
+}
+
+// Streams a record per executed instruction. The first write error stops the trace and is kept for finish.
+pub struct TraceWriter {
+    writer: Box<dyn Write + Send>,
+    format: TraceFormat,
+    steps: usize,
+    error: Option<io::Error>,
+}
+
+impl TraceWriter {
+    pub fn new<T: Write + Send + 'static>(writer: T, format: TraceFormat) -> TraceWriter {
+        let mut trace = TraceWriter {
+            writer: Box::new(writer),
+            format,
+            steps: 0,
+            error: None,
+        };
+        if format == TraceFormat::Binary {
+            let result = trace.writer.write_all(BINARY_MAGIC).and_then(|_| trace.writer.write_all(&[BINARY_VERSION]));
+            trace.error = result.err();
+        }
+        trace
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn record<W: Word>(&mut self, step: &Step<W>) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = match self.format {
+            TraceFormat::JsonLines => writeln!(self.writer, "{}", to_json(self.steps, step)),
+            TraceFormat::Binary => self.writer.write_all(&to_binary(self.steps, step)),
+        };
+        self.error = result.err();
+        self.steps += 1;
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => self.writer.flush(),
+        }
+    }
+}
+
+fn to_json<W: Word>(step_number: usize, step: &Step<W>) -> String {
+    let mut json = format!("{{\"step\":{},\"ip\":{},\"opcode\":\"{}\"", step_number, step.address, step.opcode.mnemonic());
+    let modes: Vec<String> = step.parameter_modes.iter().map(|mode| mode.code().to_string()).collect();
+    let addresses: Vec<String> = step.parameter_positions.iter().map(|position| position.to_string()).collect();
+    let reads: Vec<String> = step.reads.iter().map(|(address, value)| format!("[{},{}]", address, value)).collect();
+    let _ = write!(json, ",\"modes\":[{}],\"addresses\":[{}],\"reads\":[{}]", modes.join(","), addresses.join(","), reads.join(","));
+    let writes: Vec<String> = step.writes.iter().map(|(address, value)| format!("[{},{}]", address, value)).collect();
+    let _ = write!(json, ",\"writes\":[{}]", writes.join(","));
+    let _ = write!(json, ",\"jump\":{}", json_option(step.jump));
+    let _ = write!(json, ",\"input\":{}", json_option(step.input.as_ref()));
+    let _ = write!(json, ",\"output\":{}", json_option(step.output.as_ref()));
+    let _ = write!(json, ",\"rb\":{}}}", step.relative_base);
+    json
+}
+
+fn json_option<T: ToString>(value: Option<T>) -> String {
+    value.map_or("null".to_string(), |value| value.to_string())
+}
+
+// Addresses and counts are LEB128 varints. Words are their two's complement bytes, preceded by how many there are.
+fn to_binary<W: Word>(step_number: usize, step: &Step<W>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let flags = [
+        (step.jump.is_some(), HAS_JUMP),
+        (step.input.is_some(), HAS_INPUT),
+        (step.output.is_some(), HAS_OUTPUT),
+    ].iter().filter(|(present, _)| *present).fold(0, |flags, (_, flag)| flags | flag);
+
+    push_unsigned(&mut bytes, step_number);
+    push_unsigned(&mut bytes, step.address);
+    bytes.push(step.opcode.code() as u8);
+    bytes.push(flags);
+    for (mode, position) in step.parameter_modes.iter().zip(&step.parameter_positions) {
+        bytes.push(mode.code() as u8);
+        push_unsigned(&mut bytes, *position);
+    }
+    push_unsigned(&mut bytes, step.reads.len());
+    for (address, value) in &step.reads {
+        push_unsigned(&mut bytes, *address);
+        push_word(&mut bytes, value);
+    }
+    push_unsigned(&mut bytes, step.writes.len());
+    for (address, value) in &step.writes {
+        push_unsigned(&mut bytes, *address);
+        push_word(&mut bytes, value);
+    }
+    if let Some(target) = step.jump {
+        push_unsigned(&mut bytes, target);
+    }
+    if let Some(input) = &step.input {
+        push_word(&mut bytes, input);
+    }
+    if let Some(output) = &step.output {
+        push_word(&mut bytes, output);
+    }
+    push_unsigned(&mut bytes, step.relative_base);
+    bytes
+}
+
+fn push_unsigned(bytes: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn push_word<W: Word>(bytes: &mut Vec<u8>, value: &W) {
+    let value_bytes = value.to_bytes();
+    push_unsigned(bytes, value_bytes.len());
+    bytes.extend(value_bytes);
+}
+
+// Reads back a binary trace one record at a time
+pub struct TraceReader<R: Read, W: Word = i64> {
+    reader: R,
+    opcodes: OpcodeRegistry<W>,
+}
+
+impl<R: Read, W: Word> TraceReader<R, W> {
+    pub fn new(reader: R) -> io::Result<TraceReader<R, W>> {
+        TraceReader::with_opcodes(reader, OpcodeRegistry::new())
+    }
+
+    // Reads a trace of a computer that ran with custom opcodes or an unknown opcode handler
+    pub fn with_opcodes(mut reader: R, opcodes: OpcodeRegistry<W>) -> io::Result<TraceReader<R, W>> {
+        let mut header = [0; 5];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != BINARY_MAGIC {
+            return Err(invalid("not an Intcode trace"));
+        }
+        if header[4] != BINARY_VERSION {
+            return Err(invalid(&format!("unsupported trace version {}", header[4])));
+        }
+        Ok(TraceReader { reader, opcodes })
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0];
+        match self.reader.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        self.read_byte()?.ok_or_else(|| invalid("trace ends part way through a record"))
+    }
+
+    fn unsigned(&mut self) -> io::Result<usize> {
+        let mut value: usize = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= usize::BITS {
+                return Err(invalid("varint is too long"));
+            }
+            value |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn word(&mut self) -> io::Result<W> {
+        // The length is only trusted as far as the trace really goes, so a corrupt one cannot force a huge allocation
+        let length = self.unsigned()?;
+        let mut bytes = Vec::new();
+        (&mut self.reader).take(length as u64).read_to_end(&mut bytes)?;
+        if bytes.len() < length {
+            return Err(invalid("trace ends part way through a record"));
+        }
+        W::from_bytes(&bytes).ok_or_else(|| invalid("value does not fit in the word"))
+    }
+
+    fn record(&mut self, first_byte: u8) -> io::Result<TraceRecord<W>> {
+        let mut step_number = (first_byte & 0x7f) as usize;
+        if first_byte & 0x80 != 0 {
+            step_number |= self.unsigned()? << 7;
+        }
+        let address = self.unsigned()?;
+        let opcode_code = self.byte()?;
+        let opcode = self.opcodes.recorded(opcode_code as usize).ok_or_else(|| invalid(&format!("unknown opcode {}", opcode_code)))?;
+        let flags = self.byte()?;
+
+        let mut parameter_modes = Vec::new();
+        let mut parameter_positions = Vec::new();
+        for _ in 0..opcode.parameter_count() {
+            let mode_code = self.byte()?;
+            parameter_modes.push(ParameterMode::from(mode_code as usize).ok_or_else(|| invalid(&format!("unknown parameter mode {}", mode_code)))?);
+            parameter_positions.push(self.unsigned()?);
+        }
+        let mut reads = Vec::new();
+        for _ in 0..self.unsigned()? {
+            reads.push((self.unsigned()?, self.word()?));
+        }
+        let mut writes = Vec::new();
+        for _ in 0..self.unsigned()? {
+            writes.push((self.unsigned()?, self.word()?));
+        }
+        let jump = match flags & HAS_JUMP != 0 {
+            true => Some(self.unsigned()?),
+            false => None,
+        };
+        let input = match flags & HAS_INPUT != 0 {
+            true => Some(self.word()?),
+            false => None,
+        };
+        let output = match flags & HAS_OUTPUT != 0 {
+            true => Some(self.word()?),
+            false => None,
+        };
+        let relative_base = self.unsigned()?;
+
+        Ok(TraceRecord {
+            step_number,
+            step: Step { address, opcode, parameter_modes, parameter_positions, reads, writes, jump, input, output, relative_base },
+        })
+    }
+}
+
+impl<R: Read, W: Word> Iterator for TraceReader<R, W> {
+    type Item = io::Result<TraceRecord<W>>;
+
+    fn next(&mut self) -> Option<io::Result<TraceRecord<W>>> {
+        match self.read_byte() {
+            Ok(Some(first_byte)) => Some(self.record(first_byte)),
+            Ok(None) => None,
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::UnknownOpcodeAction;
+    use crate::{parse_program, BigInt, CustomOpcode, IntcodeComputer};
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone)]
+    #[derive(Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn contents(&self) -> Vec<u8> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    fn trace<W: Word>(program: &[W], inputs: Vec<W>, format: TraceFormat) -> Vec<u8> {
+        let buffer = SharedBuffer::default();
+        let mut computer = IntcodeComputer::<W>::default();
+        computer.load(program);
+        computer.set_trace(Some(TraceWriter::new(buffer.clone(), format)));
+
+        computer.run(Some(inputs)).unwrap();
+        computer.set_trace(None).unwrap().finish().unwrap();
+
+        buffer.contents()
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let json = String::from_utf8(trace::<i64>(&[109,10,21101,5,-3,0,204,0,99,0,0], vec![], TraceFormat::JsonLines)).unwrap();
+
+        assert_eq!(json.lines().collect::<Vec<&str>>(), vec![
+            r#"{"step":0,"ip":0,"opcode":"RELATIVEBASE","modes":[1],"addresses":[1],"reads":[[1,10]],"writes":[],"jump":null,"input":null,"output":null,"rb":10}"#,
+            r#"{"step":1,"ip":2,"opcode":"ADD","modes":[1,1,2],"addresses":[3,4,10],"reads":[[3,5],[4,-3]],"writes":[[10,2]],"jump":null,"input":null,"output":null,"rb":10}"#,
+            r#"{"step":2,"ip":6,"opcode":"OUTPUT","modes":[2],"addresses":[10],"reads":[[10,2]],"writes":[],"jump":null,"input":null,"output":2,"rb":10}"#,
+        ]);
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let program = vec![3,11,1001,11,-1,11,4,11,1005,11,0,0];
+        let mut computer = IntcodeComputer::new();
+        computer.load(&program);
+        computer.push_input(2);
+        let mut steps = vec![];
+        while let Ok(crate::StepOutcome::Executed(step)) = computer.step() {
+            steps.push(step);
+        }
+
+        let bytes = trace(&program, vec![2], TraceFormat::Binary);
+        let records: Vec<TraceRecord> = TraceReader::new(bytes.as_slice()).unwrap().map(|record| record.unwrap()).collect();
+
+        assert_eq!(&bytes[..5], b"ICTR\x03");
+        assert_eq!(records.len(), steps.len());
+        for (index, (record, step)) in records.iter().zip(steps).enumerate() {
+            assert_eq!(record.step_number, index);
+            assert_eq!(record.step, step);
+        }
+    }
+
+    #[test]
+    fn test_binary_round_trip_with_custom_opcodes() {
+        let triple = CustomOpcode { code: 20, mnemonic: "TRIPLE", parameter_count: 2, write_parameters: &[1] };
+        let mut opcodes = OpcodeRegistry::new();
+        opcodes.register(triple, |context| context.write(context.parameter(0) * 3)).unwrap();
+        opcodes.set_unknown_handler(|_, _| UnknownOpcodeAction::Skip);
+        // Skips the unknown 42, then triples 5 into [9] and outputs it
+        let program = vec![42,120,5,9,4,9,99,0,0,0];
+        let buffer = SharedBuffer::default();
+        let mut computer = IntcodeComputer::new();
+        computer.set_opcodes(opcodes.clone());
+        computer.load(&program);
+        computer.set_trace(Some(TraceWriter::new(buffer.clone(), TraceFormat::Binary)));
+
+        assert_eq!(computer.run(None).unwrap(), vec![15]);
+        computer.set_trace(None).unwrap().finish().unwrap();
+        let bytes = buffer.contents();
+        let records: Vec<TraceRecord> = TraceReader::with_opcodes(bytes.as_slice(), opcodes).unwrap().map(|record| record.unwrap()).collect();
+
+        assert_eq!(records.iter().map(|record| record.step.opcode.mnemonic()).collect::<Vec<&str>>(), vec!["SKIP", "TRIPLE", "OUTPUT"]);
+        assert_eq!(records[1].step.writes, vec![(9, 15)]);
+        assert!(TraceReader::<_>::new(bytes.as_slice()).unwrap().next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_reader_rejects_other_data() {
+        assert!(TraceReader::<_>::new(&b"ICTR\x02"[..]).is_err());
+        assert!(TraceReader::<_>::new(&b"{\"step\":0}"[..]).is_err());
+
+        let mut reader = TraceReader::<_>::new(&b"ICTR\x03\x00\x00"[..]).unwrap();
+        assert!(reader.next().unwrap().is_err());
+
+        // An output whose one read claims a value of 2^63 bytes
+        let huge_length = b"ICTR\x03\x00\x00\x04\x00\x00\x05\x01\x05\x80\x80\x80\x80\x80\x80\x80\x80\x80\x01";
+        let error = TraceReader::<_>::new(&huge_length[..]).unwrap().next().unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "trace ends part way through a record");
+    }
+
+    #[test]
+    fn test_day_9_traces_diverge_on_input() {
+        let program: Vec<i64> = parse_program(&fs::read_to_string("input_day_9.txt").unwrap()).unwrap();
+        let test_mode = trace(&program, vec![1], TraceFormat::Binary);
+        let boost_mode = trace(&program, vec![2], TraceFormat::Binary);
+
+        let divergence = TraceReader::<_>::new(test_mode.as_slice()).unwrap()
+            .zip(TraceReader::<_>::new(boost_mode.as_slice()).unwrap())
+            .position(|(left, right)| left.unwrap() != right.unwrap())
+            .unwrap();
+
+        let record = TraceReader::<_>::new(test_mode.as_slice()).unwrap().nth(divergence).unwrap().unwrap();
+        assert_eq!(record.step.input, Some(1));
+    }
+
+    #[test]
+    fn test_big_word_values() {
+        let huge: BigInt = "1000000000000000000000000000000".parse().unwrap();
+        let program = vec![BigInt::from(1002i64), BigInt::from(5i64), huge.clone(), BigInt::from(6i64), BigInt::from(99i64), huge.clone(), BigInt::zero()];
+
+        let bytes = trace(&program, vec![], TraceFormat::Binary);
+        let records: Vec<TraceRecord<BigInt>> = TraceReader::new(bytes.as_slice()).unwrap().map(|record| record.unwrap()).collect();
+
+        assert_eq!(records[0].step.writes, vec![(6, huge.multiply_checked(&huge).unwrap())]);
+        assert!(TraceReader::<_, i64>::new(bytes.as_slice()).unwrap().next().unwrap().is_err());
+    }
+}
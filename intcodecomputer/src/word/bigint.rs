@@ -0,0 +1,354 @@
+use super::{trim_twos_complement, ParseWordError, Word};
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+const DECIMAL_CHUNK: u32 = 1_000_000_000;
+const DECIMAL_CHUNK_DIGITS: usize = 9;
+
+// Sign and magnitude, with the magnitude in little endian 32 bit limbs and no high zero limbs.
+// Zero is an empty magnitude and never negative, so derived equality and hashing are exact.
+#[derive(Debug)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(Clone, Default)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> BigInt {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        BigInt {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    fn from_magnitude_u128(negative: bool, mut value: u128) -> BigInt {
+        let mut magnitude = vec![];
+        while value != 0 {
+            magnitude.push(value as u32);
+            value >>= 32;
+        }
+        BigInt::from_parts(negative, magnitude)
+    }
+}
+
+fn compare_magnitudes(left: &[u32], right: &[u32]) -> Ordering {
+    left.len().cmp(&right.len())
+        .then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add_magnitudes(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut sum = Vec::with_capacity(left.len().max(right.len()) + 1);
+    let mut carry = 0u64;
+    for index in 0..left.len().max(right.len()) {
+        let total = *left.get(index).unwrap_or(&0) as u64 + *right.get(index).unwrap_or(&0) as u64 + carry;
+        sum.push(total as u32);
+        carry = total >> 32;
+    }
+    if carry != 0 {
+        sum.push(carry as u32);
+    }
+    sum
+}
+
+// The larger magnitude must come first
+fn subtract_magnitudes(larger: &[u32], smaller: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(larger.len());
+    let mut borrow = 0i64;
+    for (index, &limb) in larger.iter().enumerate() {
+        let mut total = limb as i64 - *smaller.get(index).unwrap_or(&0) as i64 - borrow;
+        borrow = match total < 0 {
+            true => {
+                total += 1 << 32;
+                1
+            },
+            false => 0,
+        };
+        difference.push(total as u32);
+    }
+    difference
+}
+
+fn multiply_magnitudes(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut product = vec![0u32; left.len() + right.len()];
+    for (left_index, &left_limb) in left.iter().enumerate() {
+        let mut carry = 0u64;
+        for (right_index, &right_limb) in right.iter().enumerate() {
+            let total = product[left_index + right_index] as u64 + left_limb as u64 * right_limb as u64 + carry;
+            product[left_index + right_index] = total as u32;
+            carry = total >> 32;
+        }
+        product[left_index + right.len()] = carry as u32;
+    }
+    product
+}
+
+// Multiplies in place by a small factor, then adds a small value
+fn multiply_add_small(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for limb in magnitude.iter_mut() {
+        let total = *limb as u64 * factor as u64 + carry;
+        *limb = total as u32;
+        carry = total >> 32;
+    }
+    if carry != 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+// Divides in place and returns the remainder
+fn divide_small(magnitude: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+    for limb in magnitude.iter_mut().rev() {
+        let current = (remainder << 32) | *limb as u64;
+        *limb = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+    remainder as u32
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&self.magnitude, &other.magnitude),
+            (true, true) => compare_magnitudes(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.magnitude.is_empty() {
+            return write!(f, "0");
+        }
+        let mut magnitude = self.magnitude.clone();
+        let mut chunks = vec![];
+        while !magnitude.is_empty() {
+            chunks.push(divide_small(&mut magnitude, DECIMAL_CHUNK));
+        }
+        let mut text = match self.negative {
+            true => format!("-{}", chunks.pop().unwrap()),
+            false => chunks.pop().unwrap().to_string(),
+        };
+        for chunk in chunks.iter().rev() {
+            text.push_str(&format!("{:0width$}", chunk, width = DECIMAL_CHUNK_DIGITS));
+        }
+        f.pad(&text)
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = ParseWordError;
+
+    fn from_str(text: &str) -> Result<BigInt, ParseWordError> {
+        let error = || ParseWordError { text: text.to_string() };
+        let trimmed = text.trim();
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+            return Err(error());
+        }
+
+        let mut magnitude = vec![];
+        let first_chunk = match digits.len() % DECIMAL_CHUNK_DIGITS {
+            0 => DECIMAL_CHUNK_DIGITS,
+            length => length,
+        };
+        let mut start = 0;
+        let mut end = first_chunk;
+        while start < digits.len() {
+            let chunk = digits[start..end].parse::<u32>().map_err(|_| error())?;
+            multiply_add_small(&mut magnitude, 10u32.pow((end - start) as u32), chunk);
+            start = end;
+            end += DECIMAL_CHUNK_DIGITS;
+        }
+        Ok(BigInt::from_parts(negative, magnitude))
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> BigInt {
+        BigInt::from_magnitude_u128(value < 0, value.unsigned_abs() as u128)
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(value: i128) -> BigInt {
+        BigInt::from_magnitude_u128(value < 0, value.unsigned_abs())
+    }
+}
+
+// Arbitrary precision never overflows, so checked and wrapping arithmetic agree
+impl Word for BigInt {
+    fn from_i64(value: i64) -> Self {
+        BigInt::from(value)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let magnitude = self.magnitude.iter().rev().fold(0i128, |total, &limb| (total << 32) | limb as i128);
+        let value = match self.negative {
+            true => -magnitude,
+            false => magnitude,
+        };
+        i64::try_from(value).ok()
+    }
+
+    fn parse_word(text: &str) -> Result<Self, ParseWordError> {
+        text.parse::<BigInt>()
+    }
+
+    fn add_checked(&self, other: &Self) -> Option<Self> {
+        Some(self.add_wrapping(other))
+    }
+
+    fn multiply_checked(&self, other: &Self) -> Option<Self> {
+        Some(self.multiply_wrapping(other))
+    }
+
+    fn add_wrapping(&self, other: &Self) -> Self {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, add_magnitudes(&self.magnitude, &other.magnitude));
+        }
+        match compare_magnitudes(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::from_parts(other.negative, subtract_magnitudes(&other.magnitude, &self.magnitude)),
+            _ => BigInt::from_parts(self.negative, subtract_magnitudes(&self.magnitude, &other.magnitude)),
+        }
+    }
+
+    fn multiply_wrapping(&self, other: &Self) -> Self {
+        BigInt::from_parts(self.negative != other.negative, multiply_magnitudes(&self.magnitude, &other.magnitude))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self.magnitude.iter().flat_map(|limb| limb.to_le_bytes().to_vec()).collect();
+        bytes.push(0);
+        if self.negative {
+            negate_bytes(&mut bytes);
+        }
+        trim_twos_complement(bytes)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut bytes = bytes.to_vec();
+        let negative = bytes.last().is_some_and(|last| last & 0x80 != 0);
+        if negative {
+            negate_bytes(&mut bytes);
+        }
+        let magnitude = bytes.chunks(4)
+            .map(|chunk| chunk.iter().rev().fold(0u32, |limb, &byte| (limb << 8) | byte as u32))
+            .collect();
+        Some(BigInt::from_parts(negative, magnitude))
+    }
+
+    fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    fn from_usize(value: usize) -> Self {
+        BigInt::from_magnitude_u128(false, value as u128)
+    }
+}
+
+// Two's complement negation: invert every bit then add one
+fn negate_bytes(bytes: &mut [u8]) {
+    let mut carry = true;
+    for byte in bytes.iter_mut() {
+        *byte = !*byte;
+        if carry {
+            let (sum, overflowed) = byte.overflowing_add(1);
+            *byte = sum;
+            carry = overflowed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(text: &str) -> BigInt {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        for text in ["0", "7", "-7", "4294967296", "-18446744073709551616", "123456789012345678901234567890123456789"].iter() {
+            assert_eq!(big(text).to_string(), *text);
+        }
+        assert_eq!(big("+0012").to_string(), "12");
+        assert_eq!(big("-0").to_string(), "0");
+        assert!("".parse::<BigInt>().is_err());
+        assert!("12a".parse::<BigInt>().is_err());
+        assert!("-".parse::<BigInt>().is_err());
+    }
+
+    #[test]
+    fn test_add() {
+        assert_eq!(big("4294967295").add_wrapping(&big("1")), big("4294967296"));
+        assert_eq!(big("5").add_wrapping(&big("-8")), big("-3"));
+        assert_eq!(big("-5").add_wrapping(&big("8")), big("3"));
+        assert_eq!(big("-5").add_wrapping(&big("5")), BigInt::zero());
+        assert_eq!(big("-4294967296").add_wrapping(&big("-1")), big("-4294967297"));
+    }
+
+    #[test]
+    fn test_multiply() {
+        assert_eq!(big("1000000000000000000000000000000").multiply_wrapping(&big("-1000000000000000000000000000000")), big(&format!("-1{}", "0".repeat(60))));
+        assert_eq!(big("-3").multiply_wrapping(&big("-4")), big("12"));
+        assert_eq!(big("0").multiply_wrapping(&big("-4")), BigInt::zero());
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut values = vec![big("10"), big("-10000000000"), big("0"), big("-3"), big("10000000000")];
+        values.sort();
+
+        assert_eq!(values, vec![big("-10000000000"), big("-3"), big("0"), big("10"), big("10000000000")]);
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(BigInt::from_i64(i64::MIN).to_i64(), Some(i64::MIN));
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        assert_eq!(big("4294967296").to_usize(), Some(4294967296));
+        assert_eq!(big("-1").to_usize(), None);
+        assert_eq!(big("9223372036854775807").to_usize(), Some(i64::MAX as usize));
+        assert_eq!(big("9223372036854775808").to_usize(), None);
+    }
+
+    #[test]
+    fn test_bytes() {
+        for value in [0, 1, -1, 127, 128, -128, -129, i64::MAX, i64::MIN].iter() {
+            assert_eq!(BigInt::from_i64(*value).to_bytes(), value.to_bytes());
+        }
+        let large = big("-123456789012345678901234567890123456789");
+        assert_eq!(BigInt::from_bytes(&large.to_bytes()), Some(large));
+    }
+}
@@ -0,0 +1,168 @@
+pub mod bigint;
+pub use bigint::BigInt;
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+#[derive(Debug)]
+#[derive(PartialEq)]
+#[derive(Clone)]
+pub struct ParseWordError {
+    pub text: String,
+}
+
+impl fmt::Display for ParseWordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Not a valid word: {:?}", self.text)
+    }
+}
+
+impl Error for ParseWordError {}
+
+// The integer type memory cells, inputs and outputs are made of
+pub trait Word: Clone + Ord + Hash + Default + fmt::Debug + fmt::Display + Send + Sync + 'static {
+    fn from_i64(value: i64) -> Self;
+    fn to_i64(&self) -> Option<i64>;
+    fn parse_word(text: &str) -> Result<Self, ParseWordError>;
+
+    fn add_checked(&self, other: &Self) -> Option<Self>;
+    fn multiply_checked(&self, other: &Self) -> Option<Self>;
+    fn add_wrapping(&self, other: &Self) -> Self;
+    fn multiply_wrapping(&self, other: &Self) -> Self;
+
+    // Little endian two's complement in as few bytes as hold the value
+    fn to_bytes(&self) -> Vec<u8>;
+    // None when the value does not fit in the word
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+
+    fn zero() -> Self {
+        Self::from_i64(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+
+    fn is_negative(&self) -> bool {
+        *self < Self::zero()
+    }
+
+    fn from_usize(value: usize) -> Self {
+        Self::from_i64(value as i64)
+    }
+
+    // Capped at i64::MAX for every word, which leaves room to add an offset to any address
+    fn to_usize(&self) -> Option<usize> {
+        self.to_i64().and_then(|value| usize::try_from(value).ok())
+    }
+}
+
+macro_rules! primitive_word {
+    ($word:ty) => {
+        impl Word for $word {
+            fn from_i64(value: i64) -> Self {
+                <$word>::from(value)
+            }
+
+            fn to_i64(&self) -> Option<i64> {
+                i64::try_from(*self).ok()
+            }
+
+            fn parse_word(text: &str) -> Result<Self, ParseWordError> {
+                text.trim().parse::<$word>().map_err(|_| ParseWordError { text: text.to_string() })
+            }
+
+            fn add_checked(&self, other: &Self) -> Option<Self> {
+                self.checked_add(*other)
+            }
+
+            fn multiply_checked(&self, other: &Self) -> Option<Self> {
+                self.checked_mul(*other)
+            }
+
+            fn add_wrapping(&self, other: &Self) -> Self {
+                self.wrapping_add(*other)
+            }
+
+            fn multiply_wrapping(&self, other: &Self) -> Self {
+                self.wrapping_mul(*other)
+            }
+
+            fn to_bytes(&self) -> Vec<u8> {
+                trim_twos_complement(self.to_le_bytes().to_vec())
+            }
+
+            fn from_bytes(bytes: &[u8]) -> Option<Self> {
+                let mut buffer = match bytes.last() {
+                    Some(last) if last & 0x80 != 0 => [0xff; std::mem::size_of::<$word>()],
+                    _ => [0; std::mem::size_of::<$word>()],
+                };
+                if bytes.len() > buffer.len() {
+                    return None;
+                }
+                buffer[..bytes.len()].copy_from_slice(bytes);
+                Some(<$word>::from_le_bytes(buffer))
+            }
+        }
+    };
+}
+
+primitive_word!(i64);
+primitive_word!(i128);
+
+// Drops high bytes that only repeat the sign
+pub(crate) fn trim_twos_complement(mut bytes: Vec<u8>) -> Vec<u8> {
+    while bytes.len() > 1 {
+        let last = bytes[bytes.len() - 1];
+        let sign_of_next = bytes[bytes.len() - 2] & 0x80;
+        match (last, sign_of_next) {
+            (0x00, 0) | (0xff, 0x80) => bytes.pop(),
+            _ => break,
+        };
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<W: Word>(value: W) -> Option<W> {
+        W::from_bytes(&value.to_bytes())
+    }
+
+    #[test]
+    fn test_bytes_are_minimal() {
+        assert_eq!(0i64.to_bytes(), vec![0]);
+        assert_eq!(127i64.to_bytes(), vec![0x7f]);
+        assert_eq!(128i64.to_bytes(), vec![0x80, 0]);
+        assert_eq!((-1i64).to_bytes(), vec![0xff]);
+        assert_eq!((-129i128).to_bytes(), vec![0x7f, 0xff]);
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        for value in [0, 1, -1, 255, -256, i64::MAX, i64::MIN].iter() {
+            assert_eq!(round_trip(*value), Some(*value));
+            assert_eq!(round_trip(*value as i128), Some(*value as i128));
+        }
+        assert_eq!(i64::from_bytes(&i128::MAX.to_bytes()), None);
+    }
+
+    #[test]
+    fn test_addresses() {
+        assert_eq!(5i64.to_usize(), Some(5));
+        assert_eq!((-5i64).to_usize(), None);
+        assert_eq!(i128::MAX.to_usize(), None);
+        assert_eq!(i128::from_usize(7), 7);
+    }
+
+    #[test]
+    fn test_parse_word() {
+        assert_eq!(i64::parse_word(" -12 "), Ok(-12));
+        assert_eq!(i64::parse_word("1e3"), Err(ParseWordError { text: "1e3".to_string() }));
+        assert_eq!(i128::parse_word("170141183460469231731687303715884105727"), Ok(i128::MAX));
+    }
+}